@@ -179,7 +179,7 @@ fn bench_random(diff: Difficulty, b: &mut Bencher) {
     b.iter(|| {
         let seed = initial_seed + n;
         let config = Config::from_difficulty(diff, Some(seed));
-        let solver = Solver::new(config);
+        let solver = Solver::new(config.clone());
         match std::panic::catch_unwind(|| solver.solve(&mut MockMinesweeper::new(config))) {
             Ok(Err(MinesweeperError::RevealedBomb(_))) => (),
             Ok(Ok(())) => solved += 1,
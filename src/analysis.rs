@@ -0,0 +1,144 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Certainty {
+    Safe,
+    Mine,
+    Uncertain,
+}
+
+/// Per-cell mine probabilities of a position, as computed by `Solver::analyze`.
+/// Revealed cells have neither a probability nor a certainty.
+#[derive(Clone, Debug)]
+pub struct Analysis {
+    configurations: Option<Real>,
+    exact: bool,
+    probabilities: Vec<Option<Real>>,
+    certainties: Vec<Option<Certainty>>,
+}
+
+impl Analysis {
+    pub fn new(
+        state: &MinesweeperState,
+        configurations: Option<Real>,
+        exact: bool,
+        mut probabilities: Vec<Option<Real>>,
+    ) -> Self {
+        let certainties = state
+            .board()
+            .iter()
+            .zip(probabilities.iter_mut())
+            .map(|(status, p)| match status {
+                Status::Known(_) | Status::Masked => None,
+                Status::Flagged => {
+                    *p = Some(Real::one());
+                    Some(Certainty::Mine)
+                }
+                Status::Marked => {
+                    *p = Some(Real::zero());
+                    Some(Certainty::Safe)
+                }
+                Status::Question | Status::Unknown => Some(Certainty::Uncertain),
+            })
+            .collect();
+        Self {
+            configurations,
+            exact,
+            probabilities,
+            certainties,
+        }
+    }
+
+    /// Number of mine layouts consistent with the analysed position, or
+    /// `None` if the solver ran out of budget and only estimated, or if the
+    /// number is too large for a float without the `exact` feature.
+    pub fn configurations(&self) -> Option<&Real> {
+        self.configurations.as_ref()
+    }
+
+    /// Whether the probabilities were computed exactly rather than estimated.
+    #[inline]
+    pub fn is_exact(&self) -> bool {
+        self.exact
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.probabilities.len()
+    }
+
+    pub fn probabilities(&self) -> &[Option<Real>] {
+        &self.probabilities
+    }
+
+    #[inline]
+    pub fn probability(&self, idx: Index) -> Option<&Real> {
+        self.probabilities[idx].as_ref()
+    }
+
+    #[inline]
+    pub fn certainty(&self, idx: Index) -> Option<Certainty> {
+        self.certainties[idx]
+    }
+
+    pub fn filter_certainty(&self, certainty: Certainty) -> impl Iterator<Item = Index> + '_ {
+        self.certainties
+            .iter()
+            .enumerate()
+            .filter(move |(_, &c)| c == Some(certainty))
+            .map(|(idx, _)| idx)
+    }
+}
+
+/// The cell opened by `Solver::solve_next`, either directly or by chording a
+/// neighbour, with its mine probability.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    index: Index,
+    probability: Real,
+    exact: bool,
+}
+
+impl Move {
+    pub(crate) fn new((probability, index): ScoredIndex, exact: bool) -> Self {
+        Self {
+            index,
+            probability,
+            exact,
+        }
+    }
+
+    #[inline]
+    pub fn index(&self) -> Index {
+        self.index
+    }
+
+    #[inline]
+    pub fn probability(&self) -> &Real {
+        &self.probability
+    }
+
+    /// Whether the probability was computed exactly rather than estimated
+    /// after the solver ran out of budget.
+    #[inline]
+    pub fn is_exact(&self) -> bool {
+        self.exact
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+/// A position written as plain text, one row per line:
+///
+/// ```text
+/// mines 10
+/// 1F1..
+/// 111*.
+/// 000?.
+/// ```
+///
+/// Digits are revealed counts, `F` a flag, `.` an unknown cell, `?` a
+/// question mark, `*` an unknown cell known to be a mine and `#` a masked
+/// cell. Spaces between cells are ignored. The `mines` line is optional;
+/// without it, the mines are the flagged and `*` cells. A `wrap` line before
+/// the rows makes the board wrap around, and a `hex` line makes it a hex
+/// grid, usually written with odd rows indented. Configs read this way have
+/// seed 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Board {
+    config: Config,
+    state: MinesweeperState,
+    mines: Vec<Index>,
+}
+
+impl Board {
+    pub fn new(config: Config, state: MinesweeperState) -> Self {
+        Self {
+            config,
+            state,
+            mines: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    #[inline]
+    pub fn state(&self) -> &MinesweeperState {
+        &self.state
+    }
+
+    /// The cells written as `*`.
+    #[inline]
+    pub fn mines(&self) -> &[Index] {
+        &self.mines
+    }
+
+    /// The full layout, if every mine that is not flagged is written as `*`,
+    /// e.g. to play the position on with `Game::with_bombs`.
+    pub fn bombs(&self) -> Option<Vec<bool>> {
+        let mut bombs = vec![false; self.config.size()];
+        let flagged = (0..self.config.size()).filter(|&idx| self.state.get(idx) == Status::Flagged);
+        for idx in self.mines.iter().copied().chain(flagged) {
+            bombs[idx] = true;
+        }
+        (bombs.iter().filter(|&&b| b).count() == self.config.mines()).then_some(bombs)
+    }
+
+    /// The position in the text format. Fails with `UnsupportedLayout` for a
+    /// 3D board, a grid other than square or hex, or a custom topology, which
+    /// the format cannot express.
+    pub fn to_text(&self) -> MsResult<String> {
+        let grid = self.config.grid();
+        if self.config.depth() > 1
+            || self.config.topology().is_some()
+            || !matches!(grid, Grid::Square | Grid::Hex)
+        {
+            return Err(MinesweeperError::UnsupportedLayout);
+        }
+        let mut text = format!("mines {}\n", self.config.mines());
+        if self.config.is_wrapped() {
+            text += "wrap\n";
+        }
+        let hex = grid == Grid::Hex;
+        if hex {
+            text += "hex\n";
+        }
+        for row in 0..self.config.width() {
+            for col in 0..self.config.length() {
+                if hex && (col > 0 || row % 2 == 1) {
+                    text.push(' ');
+                }
+                let idx = self.config.from_rc(row, col);
+                match self.state.get(idx) {
+                    Status::Known(count) => text += &count.to_string(),
+                    Status::Flagged => text.push('F'),
+                    Status::Question => text.push('?'),
+                    Status::Marked | Status::Unknown if self.mines.contains(&idx) => text.push('*'),
+                    Status::Marked | Status::Unknown => text.push('.'),
+                    Status::Masked => text.push('#'),
+                }
+            }
+            text.push('\n');
+        }
+        Ok(text)
+    }
+
+    // Every count has at least as many mines around it as the flagged and
+    // `*` cells, and at most as many as those and the unknown cells, or the
+    // mines left for them
+    fn is_consistent(&self) -> bool {
+        let state = &self.state;
+        let left = state.flags_remaining() - self.mines.len();
+        let mut is_mine = vec![false; self.config.size()];
+        for &idx in &self.mines {
+            is_mine[idx] = true;
+        }
+        let known = |cidx: Index| state.get(cidx) == Status::Flagged || is_mine[cidx];
+        (0..self.config.size()).all(|idx| match state.get_known(idx) {
+            Some(count) => {
+                let mines = self.config.square(idx).filter(|&cidx| known(cidx)).count();
+                let unknowns = self
+                    .config
+                    .square(idx)
+                    .filter(|&cidx| !known(cidx) && state.get_known(cidx).is_none())
+                    .count();
+                (mines..=mines + min(unknowns, left)).contains(&count)
+            }
+            None => true,
+        })
+    }
+}
+
+impl FromStr for Board {
+    type Err = MinesweeperError;
+
+    /// Parse errors hold the line number, counting from 1, or 0 if the
+    /// position as a whole is inconsistent.
+    fn from_str(s: &str) -> MsResult<Self> {
+        let (mut total, mut wrap, mut grid) = (None, false, Grid::Square);
+        let mut board = Vec::new();
+        let mut mines = Vec::new();
+        let (mut width, mut length) = (0, None);
+        for (i, line) in s.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+            if line.is_empty() {
+                continue;
+            }
+            if line == "wrap" || line == "hex" {
+                if width > 0 {
+                    return Err(MinesweeperError::ParseError(i));
+                }
+                if line == "wrap" {
+                    wrap = true;
+                } else {
+                    grid = Grid::Hex;
+                }
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix("mines") {
+                if total.is_some() || width > 0 {
+                    return Err(MinesweeperError::ParseError(i));
+                }
+                let mines = rest
+                    .trim()
+                    .parse()
+                    .map_err(|_| MinesweeperError::ParseError(i))?;
+                total = Some(mines);
+                continue;
+            }
+            let start = board.len();
+            for c in line.chars().filter(|c| !c.is_whitespace()) {
+                let status = match c {
+                    '0'..='8' => Status::Known(c as usize - '0' as usize),
+                    'F' => Status::Flagged,
+                    '?' => Status::Question,
+                    '.' => Status::Unknown,
+                    '#' => Status::Masked,
+                    '*' => {
+                        mines.push(board.len());
+                        Status::Unknown
+                    }
+                    _ => return Err(MinesweeperError::ParseError(i)),
+                };
+                board.push(status);
+            }
+            if *length.get_or_insert(board.len() - start) != board.len() - start {
+                return Err(MinesweeperError::ParseError(i));
+            }
+            width += 1;
+        }
+        let length = length.unwrap_or(0);
+        if width == 0 || length == 0 {
+            return Err(MinesweeperError::ParseError(0));
+        }
+
+        let flags = board.iter().filter(|&&s| s == Status::Flagged).count();
+        let total = total.unwrap_or(flags + mines.len());
+        let unknowns = board
+            .iter()
+            .filter(|&&s| matches!(s, Status::Question | Status::Unknown))
+            .count();
+        if total < flags + mines.len() || total > flags + unknowns {
+            return Err(MinesweeperError::ParseError(0));
+        }
+        let config = Config::new(width, length, total, Some(0))?
+            .with_grid(grid)
+            .map_err(|_| MinesweeperError::ParseError(0))?;
+        let config = if wrap {
+            config
+                .with_wrap()
+                .map_err(|_| MinesweeperError::ParseError(0))?
+        } else {
+            config
+        };
+        let config = if board.contains(&Status::Masked) {
+            let mask = board.iter().map(|&s| s == Status::Masked).collect();
+            config
+                .with_mask(mask)
+                .map_err(|_| MinesweeperError::ParseError(0))?
+        } else {
+            config
+        };
+        let mut state = MinesweeperState::new(&config);
+        for (idx, &status) in board.iter().enumerate() {
+            state.set_status(idx, status);
+        }
+        let board = Self {
+            config,
+            state,
+            mines,
+        };
+        if !board.is_consistent() {
+            return Err(MinesweeperError::ParseError(0));
+        }
+        Ok(board)
+    }
+}
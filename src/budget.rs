@@ -0,0 +1,118 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+/// Limits on the backtracking done to evaluate one position. Unlimited by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Budget {
+    nodes: Option<usize>,
+    time: Option<Duration>,
+}
+
+impl Budget {
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn with_nodes(mut self, nodes: usize) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    pub fn with_time(mut self, time: Duration) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    #[inline]
+    pub fn nodes(&self) -> Option<usize> {
+        self.nodes
+    }
+
+    #[inline]
+    pub fn time(&self) -> Option<Duration> {
+        self.time
+    }
+}
+
+pub struct Meter {
+    budget: Budget,
+    start: Instant,
+    nodes: Cell<usize>,
+    exhausted: Cell<bool>,
+}
+
+impl Meter {
+    pub fn new(budget: Budget) -> Self {
+        Self {
+            budget,
+            start: Instant::now(),
+            nodes: Cell::new(0),
+            exhausted: Cell::new(false),
+        }
+    }
+
+    // Accounts for one more node, returning false once the budget is spent
+    pub fn spend(&self) -> bool {
+        let nodes = self.nodes.get() + 1;
+        self.nodes.set(nodes);
+        if self.budget.nodes.is_some_and(|n| nodes > n)
+            || self.budget.time.is_some_and(|t| self.start.elapsed() > t)
+        {
+            self.exhausted.set(true);
+        }
+        !self.exhausted.get()
+    }
+
+    #[inline]
+    pub fn exhausted(&self) -> bool {
+        self.exhausted.get()
+    }
+}
+
+impl Solver {
+    // Mine probability of every unknown cell next to a known one, averaging
+    // the density of mines left around each of its known neighbours. Cheap,
+    // but ignores how the constraints interact.
+    pub(crate) fn estimate(&self, state: &MinesweeperState) -> Vec<(Real, Index)> {
+        let density = |cidx: Index| {
+            let count = state.get_known(cidx)?;
+            let (flags, unknowns) =
+                self.square(cidx)
+                    .iter()
+                    .fold((0, 0), |(flags, unknowns), &x| match state.get(x) {
+                        Status::Flagged => (flags + 1, unknowns),
+                        Status::Unknown => (flags, unknowns + 1),
+                        _ => (flags, unknowns),
+                    });
+            let mines = count.saturating_sub(flags).min(unknowns);
+            Some(util::real(mines) / util::real(unknowns))
+        };
+        (0..self.size())
+            .filter(|&idx| state.get(idx) == Status::Unknown)
+            .filter_map(|idx| {
+                let (sum, n) = self
+                    .square(idx)
+                    .iter()
+                    .filter_map(|&cidx| density(cidx))
+                    .fold((Real::zero(), 0), |(sum, n), p| (sum + p, n + 1));
+                (n != 0).then(|| (sum / util::real(n), idx))
+            })
+            .collect()
+    }
+}
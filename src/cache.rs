@@ -0,0 +1,116 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+// Evaluations kept before the cache is emptied
+const MAX_ENTRIES: usize = 1 << 12;
+
+// A frontier component: its revealed cells with the mines they still need,
+// its unknown cells, and the flags left, which bound its layouts only up to
+// its number of unknown cells. Its evaluation depends on nothing else.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Key {
+    knowns: Vec<(Index, usize)>,
+    unknowns: Vec<Index>,
+    flags: usize,
+}
+
+impl Key {
+    fn new(solver: &Solver, state: &MinesweeperState, group: &Group) -> Self {
+        let knowns = group
+            .knowns()
+            .map(|idx| {
+                let count = state.get_known(idx).unwrap();
+                let flags = solver
+                    .square_of(state, idx, |status| status == Status::Flagged)
+                    .count();
+                (idx, count.wrapping_sub(flags))
+            })
+            .collect();
+        let unknowns = group.unknowns().collect::<Vec<Index>>();
+        let flags = min(state.flags_remaining(), unknowns.len());
+        Self {
+            knowns,
+            unknowns,
+            flags,
+        }
+    }
+}
+
+/// Evaluations of frontier components, shared by clones of a `Solver` so
+/// that components untouched by a move are not counted again.
+#[derive(Debug, Default)]
+pub struct Cache {
+    entries: Mutex<HashMap<Key, Evaluation>>,
+}
+
+impl Cache {
+    fn get(&self, key: &Key) -> Option<Evaluation> {
+        self.entries.lock().ok()?.get(key).cloned()
+    }
+
+    fn insert(&self, key: Key, eval: Evaluation) {
+        if let Ok(mut entries) = self.entries.lock() {
+            if entries.len() >= MAX_ENTRIES {
+                entries.clear();
+            }
+            entries.insert(key, eval);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().map_or(0, |entries| entries.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
+
+impl Solver {
+    // Evaluates every component of `group` separately, reusing the cached
+    // evaluation of any component seen before
+    pub(crate) fn cached_evaluation(
+        &self,
+        state: &MinesweeperState,
+        group: &Group,
+        meter: &Meter,
+    ) -> Option<Evaluation> {
+        let identity = Evaluation::new(state, bitvec![0; self.size()]);
+        group.clone().into_iter().try_fold(identity, |eval, split| {
+            let key = Key::new(self, state, &split);
+            let split = match self.cache().get(&key) {
+                Some(cached) => cached,
+                None => {
+                    let split = self.branching_evaluation(state, &split, meter)?;
+                    if !meter.exhausted() {
+                        self.cache().insert(key, split.clone());
+                    }
+                    split
+                }
+            };
+            Some(eval * split)
+        })
+    }
+}
@@ -17,28 +17,234 @@
 
 use super::*;
 
-#[derive(Clone, Copy, Debug)]
+/// With the `serde` feature, serializing a config with a custom topology
+/// fails, as the topology cannot be written.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "ConfigData")
+)]
 pub struct Config {
     width: usize,
     length: usize,
+    depth: usize,
     mines: usize,
     seed: u64,
+    wrap: bool,
+    grid: Grid,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    mask: Option<Vec<bool>>,
+    // Replaces the grid if set
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            skip_serializing_if = "Option::is_none",
+            serialize_with = "no_topology"
+        )
+    )]
+    topology: Option<Arc<dyn Topology>>,
+}
+
+impl PartialEq for Config {
+    fn eq(&self, other: &Self) -> bool {
+        let same_topology = match (&self.topology, &other.topology) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        self.width == other.width
+            && self.length == other.length
+            && self.depth == other.depth
+            && self.mines == other.mines
+            && self.seed == other.seed
+            && self.wrap == other.wrap
+            && self.grid == other.grid
+            && self.mask == other.mask
+            && same_topology
+    }
+}
+
+impl Eq for Config {}
+
+// Deserialized fields of a `Config`, checked by `Config::new`
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct ConfigData {
+    width: usize,
+    length: usize,
+    #[serde(default = "one")]
+    depth: usize,
+    mines: usize,
+    seed: u64,
+    #[serde(default)]
+    wrap: bool,
+    #[serde(default)]
+    grid: Grid,
+    #[serde(default)]
+    mask: Option<Vec<bool>>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ConfigData> for Config {
+    type Error = MinesweeperError;
+
+    fn try_from(data: ConfigData) -> MsResult<Self> {
+        let config = Self::new_3d(
+            data.width,
+            data.length,
+            data.depth,
+            data.mines,
+            Some(data.seed),
+        )?
+        .with_grid(data.grid)?;
+        let config = match data.mask {
+            Some(mask) => config.with_mask(mask)?,
+            None => config,
+        };
+        Ok(if data.wrap {
+            config.with_wrap()?
+        } else {
+            config
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+fn one() -> usize {
+    1
+}
+
+#[cfg(feature = "serde")]
+fn no_topology<S: serde::Serializer>(
+    _: &Option<Arc<dyn Topology>>,
+    _: S,
+) -> Result<S::Ok, S::Error> {
+    Err(serde::ser::Error::custom(
+        "a custom topology cannot be serialized",
+    ))
 }
 
 impl Config {
     pub fn new(width: usize, length: usize, mines: usize, seed: Option<u64>) -> MsResult<Self> {
-        if width * length < mines {
+        Self::new_3d(width, length, 1, mines, seed)
+    }
+
+    /// A board of `depth` layers of `width` rows, stacked so that a cell
+    /// also neighbours the cells above and below it and their neighbours
+    /// in the layer, 26 in all on a square grid.
+    pub fn new_3d(
+        width: usize,
+        length: usize,
+        depth: usize,
+        mines: usize,
+        seed: Option<u64>,
+    ) -> MsResult<Self> {
+        if width * length * depth < mines {
             return Err(MinesweeperError::NumberOfMinesOutOfRange);
         }
         let seed = seed.unwrap_or_else(rand::random::<u64>);
         Ok(Self {
             width,
             length,
+            depth,
             mines,
             seed,
+            wrap: false,
+            grid: Grid::Square,
+            mask: None,
+            topology: None,
         })
     }
 
+    /// A board with the cells and neighbours of `topology`, laid out as a
+    /// single row. The grid and wrapping of the config are then unused.
+    pub fn from_topology<T: 'static + Topology>(
+        topology: T,
+        mines: usize,
+        seed: Option<u64>,
+    ) -> MsResult<Self> {
+        let mut config = Self::new(1, topology.size(), mines, seed)?;
+        config.topology = Some(Arc::new(topology));
+        Ok(config)
+    }
+
+    /// The topology given to `from_topology`, if any.
+    #[inline]
+    pub fn topology(&self) -> Option<&dyn Topology> {
+        self.topology.as_deref()
+    }
+
+    /// Makes opposite edges of the board adjacent, so that every cell of a
+    /// board with at least three rows and columns has the same number of
+    /// neighbours. Fails for a hex board with an odd number of rows, whose
+    /// top and bottom rows do not fit together.
+    pub fn with_wrap(mut self) -> MsResult<Self> {
+        self.wrap = true;
+        self.check_wrap()
+    }
+
+    #[inline]
+    pub fn is_wrapped(&self) -> bool {
+        self.wrap
+    }
+
+    /// Fails for a wrapped hex board with an odd number of rows, see
+    /// `with_wrap`.
+    pub fn with_grid(mut self, grid: Grid) -> MsResult<Self> {
+        self.grid = grid;
+        self.check_wrap()
+    }
+
+    // Wrapping an odd number of hex rows would make some neighbours one-way
+    fn check_wrap(self) -> MsResult<Self> {
+        if self.wrap && self.grid == Grid::Hex && self.width % 2 == 1 {
+            return Err(MinesweeperError::InvalidTopology(self.width));
+        }
+        Ok(self)
+    }
+
+    #[inline]
+    pub fn grid(&self) -> Grid {
+        self.grid
+    }
+
+    /// Removes the cells set in `mask` from the board, to play boards of
+    /// other shapes. Removed cells never hold a mine and neighbour no cell.
+    /// Fails if `mask` does not hold one entry per cell, or leaves fewer
+    /// cells than mines.
+    pub fn with_mask(mut self, mask: Vec<bool>) -> MsResult<Self> {
+        if mask.len() != self.size() {
+            return Err(MinesweeperError::InvalidTopology(mask.len()));
+        }
+        self.mask = Some(mask);
+        if self.cells() < self.mines {
+            return Err(MinesweeperError::NumberOfMinesOutOfRange);
+        }
+        Ok(self)
+    }
+
+    // Whether `bombs` has an entry per cell and `mines` mines, none of them
+    // on a masked cell
+    pub(crate) fn is_layout(&self, bombs: &[bool]) -> bool {
+        bombs.len() == self.size()
+            && bombs.iter().filter(|&&b| b).count() == self.mines
+            && (0..self.size()).all(|idx| !(bombs[idx] && self.is_masked(idx)))
+    }
+
+    /// The first cell with the fewest neighbours, where the solver opens the
+    /// game. It is a corner unless the board wraps or has a custom topology.
+    pub fn opening(&self) -> Option<Index> {
+        (0..self.size())
+            .filter(|&idx| !self.is_masked(idx))
+            .min_by_key(|&idx| self.square(idx).count())
+    }
+
+    /// Whether `idx` was removed by `with_mask`.
+    #[inline]
+    pub fn is_masked(&self, idx: Index) -> bool {
+        self.mask.as_ref().is_some_and(|mask| mask[idx])
+    }
+
     #[inline]
     pub fn width(&self) -> usize {
         self.width
@@ -49,9 +255,25 @@ impl Config {
         self.length
     }
 
+    /// Number of layers, 1 unless made with `new_3d`.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Number of indices, including masked cells.
     #[inline]
     pub fn size(&self) -> usize {
-        self.width * self.length
+        self.width * self.length * self.depth
+    }
+
+    /// Number of cells that are not masked.
+    #[inline]
+    pub fn cells(&self) -> usize {
+        match &self.mask {
+            Some(mask) => mask.iter().filter(|&&masked| !masked).count(),
+            None => self.size(),
+        }
     }
 
     #[inline]
@@ -59,41 +281,128 @@ impl Config {
         self.mines
     }
 
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The row and column of `idx` within its layer.
     #[inline]
     pub fn as_rc(&self, idx: Index) -> (usize, usize) {
-        (idx / self.length, idx % self.length)
+        ((idx / self.length) % self.width, idx % self.length)
     }
 
+    /// The cell at `row` and `col` of the first layer.
     #[inline]
     pub fn from_rc(&self, row: usize, col: usize) -> Index {
         row * self.length + col
     }
 
+    /// The layer, row and column of `idx`. Layers are stored one after the
+    /// other.
+    #[inline]
+    pub fn as_lrc(&self, idx: Index) -> (usize, usize, usize) {
+        let (row, col) = self.as_rc(idx);
+        (idx / (self.width * self.length), row, col)
+    }
+
+    #[inline]
+    pub fn from_lrc(&self, layer: usize, row: usize, col: usize) -> Index {
+        (layer * self.width + row) * self.length + col
+    }
+
     #[inline]
     pub fn new_rng(&self) -> impl Rng {
         StdRng::seed_from_u64(self.seed)
     }
 
+    /// A cell picked uniformly at random among those not masked. Panics if
+    /// every cell is masked.
     #[inline]
     pub fn random_index(&self, rng: &mut impl Rng) -> usize {
+        if self.mask.is_some() {
+            let nth = Uniform::from(0..self.cells()).sample(rng);
+            return (0..self.size())
+                .filter(|&idx| !self.is_masked(idx))
+                .nth(nth)
+                .unwrap();
+        }
         let w = Uniform::from(0..self.width());
         let l = Uniform::from(0..self.length());
-        self.from_rc(w.sample(rng), l.sample(rng))
+        let (row, col) = (w.sample(rng), l.sample(rng));
+        let layer = if self.depth > 1 {
+            Uniform::from(0..self.depth).sample(rng)
+        } else {
+            0
+        };
+        self.from_lrc(layer, row, col)
     }
 
+    /// The cell `rows` down and `cols` right of `idx` in its layer, if it is
+    /// on the board.
+    #[inline]
+    pub fn offset(&self, idx: Index, rows: isize, cols: isize) -> Option<Index> {
+        self.offset_3d(idx, 0, rows, cols)
+    }
+
+    /// The cell `layers` deeper, `rows` down and `cols` right of `idx`, if it
+    /// is on the board.
+    #[inline]
+    pub fn offset_3d(&self, idx: Index, layers: isize, rows: isize, cols: isize) -> Option<Index> {
+        let (layer, row, col) = self.as_lrc(idx);
+        let shift = |x: usize, by: isize, n: usize| {
+            let x = x as isize + by;
+            if self.wrap {
+                Some(x.rem_euclid(n as isize) as usize)
+            } else {
+                (0..n as isize).contains(&x).then_some(x as usize)
+            }
+        };
+        Some(self.from_lrc(
+            shift(layer, layers, self.depth)?,
+            shift(row, rows, self.width)?,
+            shift(col, cols, self.length)?,
+        ))
+    }
+
+    /// The neighbours of `idx` that are not masked, or none if `idx` is.
     #[inline]
     pub fn square(&self, idx: Index) -> impl Iterator<Item = Index> + '_ {
-        let (row, col) = self.as_rc(idx);
-        let rmin = max(1, row) - 1;
-        let rmax = min(self.width() - 1, row + 1);
-        let cmin = max(1, col) - 1;
-        let cmax = min(self.length() - 1, col + 1);
-        (rmin..=rmax).flat_map(move |r| {
-            (cmin..=cmax).filter_map(move |c| {
-                let cidx = self.from_rc(r, c);
-                (cidx != idx).then(|| cidx)
-            })
-        })
+        let mut square = match &self.topology {
+            Some(topology) => topology.neighbours(idx),
+            None => self.grid_square(idx),
+        };
+        if let Some(mask) = &self.mask {
+            if mask[idx] {
+                square.clear();
+            } else {
+                square.retain(|cidx| !mask[*cidx]);
+            }
+        }
+        square.into_iter()
+    }
+
+    fn grid_square(&self, idx: Index) -> Square {
+        // On a small wrapped board, different steps can reach the same cell,
+        // which is only listed once
+        let mut square = Square::new();
+        let (row, _) = self.as_rc(idx);
+        // In the layers above and below, the cell in line with `idx` also
+        // neighbours it
+        let layers: &[isize] = if self.depth > 1 { &[-1, 0, 1] } else { &[0] };
+        for &layers in layers {
+            let in_line = (layers != 0).then_some((0, 0));
+            let steps = in_line
+                .into_iter()
+                .chain(self.grid.steps(row).iter().copied());
+            for (rows, cols) in steps {
+                match self.offset_3d(idx, layers, rows, cols) {
+                    Some(cidx) if cidx != idx && !square.contains(&cidx) => square.push(cidx),
+                    _ => (),
+                }
+            }
+        }
+        square
     }
 
     #[rustfmt::skip]
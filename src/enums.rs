@@ -18,21 +18,42 @@
 use super::*;
 
 #[derive(Clone, Copy, Debug, Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MinesweeperError {
     #[error("NumberOfMinesOutOfRange")]
     NumberOfMinesOutOfRange,
     #[error("RevealedBomb")]
     RevealedBomb(usize),
+    #[error("InconsistentState")]
+    InconsistentState,
+    #[error("GameOver")]
+    GameOver,
+    #[error("InvalidMove")]
+    InvalidMove(usize),
+    #[error("GenerationFailed")]
+    GenerationFailed,
+    #[error("ParseError")]
+    ParseError(usize),
+    #[error("InvalidTopology")]
+    InvalidTopology(usize),
+    /// The layout of the board cannot be written in the text format.
+    #[error("UnsupportedLayout")]
+    UnsupportedLayout,
 }
 
 pub type MsResult<T> = Result<T, MinesweeperError>;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Status {
     Flagged,
     Known(usize),
     Marked,
+    /// An unknown cell the player marked with a question mark.
+    Question,
     Unknown,
+    /// A cell removed by `Config::with_mask`.
+    Masked,
 }
 
 impl Default for Status {
@@ -41,7 +62,196 @@ impl Default for Status {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameStatus {
+    #[default]
+    Playing,
+    Won,
+    /// The game ended by revealing the mine at this cell.
+    Lost(Index),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Reveal(Index),
+    Flag(Index),
+    Unflag(Index),
+    /// Puts a question mark on an unknown cell, or takes it off.
+    Question(Index),
+    /// Reveals every unflagged neighbour of a revealed cell with as many
+    /// flags around it as its count.
+    Chord(Index),
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reveal(idx) => write!(f, "reveal {}", idx),
+            Self::Flag(idx) => write!(f, "flag {}", idx),
+            Self::Unflag(idx) => write!(f, "unflag {}", idx),
+            Self::Question(idx) => write!(f, "question {}", idx),
+            Self::Chord(idx) => write!(f, "chord {}", idx),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let (name, idx) = s.split_once(' ').ok_or(())?;
+        let idx = idx.trim().parse().map_err(|_| ())?;
+        match name {
+            "reveal" => Ok(Self::Reveal(idx)),
+            "flag" => Ok(Self::Flag(idx)),
+            "unflag" => Ok(Self::Unflag(idx)),
+            "question" => Ok(Self::Question(idx)),
+            "chord" => Ok(Self::Chord(idx)),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Action {
+    #[inline]
+    pub fn index(&self) -> Index {
+        match *self {
+            Self::Reveal(idx)
+            | Self::Flag(idx)
+            | Self::Unflag(idx)
+            | Self::Question(idx)
+            | Self::Chord(idx) => idx,
+        }
+    }
+
+    #[inline]
+    pub fn is_flag(&self) -> bool {
+        matches!(self, Self::Flag(_))
+    }
+}
+
+/// Local deduction rules, named as players know them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Display)]
+pub enum Technique {
+    /// A count is already met by its flags, or needs all of its unknown cells.
+    #[strum(to_string = "single")]
+    Single,
+    /// The unknown cells of one count contain those of another, and the
+    /// difference of the counts falls on the extra cells.
+    #[strum(to_string = "subset")]
+    Subset,
+    /// One count needs more mines than it can share with another, so its
+    /// unshared cells are mines and the other's unshared cells are safe.
+    #[strum(to_string = "superset")]
+    Superset,
+    /// Two adjacent 1s along an edge.
+    #[strum(to_string = "1-1")]
+    OneOne,
+    /// A 1 next to a 2 along an edge.
+    #[strum(to_string = "1-2")]
+    OneTwo,
+    #[strum(to_string = "1-2-1")]
+    OneTwoOne,
+    #[strum(to_string = "1-2-2-1")]
+    OneTwoTwoOne,
+}
+
+/// What the first reveal of a generated board is guaranteed to find.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, EnumString)]
+pub enum FirstClick {
+    /// Nothing. The first reveal may hit a mine.
+    Unsafe,
+    /// The first cell revealed is never a mine.
+    #[default]
+    Safe,
+    /// The first cell revealed and its neighbours are never mines, so it
+    /// opens a region.
+    Opening,
+    /// Mines are placed beforehand. If the first reveal hits one, it is moved
+    /// to the first free cell in reading order, as Windows Minesweeper does.
+    Relocate,
+}
+
+/// The shape of the cells of a board.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Grid {
+    /// Square cells with eight neighbours.
+    #[default]
+    Square,
+    /// Hexagonal cells with six neighbours. Rows are offset: odd rows sit
+    /// half a cell to the right of even rows, and `Config::as_rc` gives the
+    /// row and the position within it.
+    Hex,
+    /// Square cells neighbouring the cells a knight's move away.
+    Knight,
+    /// Square cells with the 24 neighbours at most two steps away in each
+    /// direction.
+    Radius2,
+}
+
+impl Grid {
+    // Steps to the neighbours of a cell in `row`
+    pub(crate) fn steps(&self, row: usize) -> &'static [(isize, isize)] {
+        const SQUARE: [(isize, isize); 8] = [
+            (-1, -1),
+            (-1, 0),
+            (-1, 1),
+            (0, -1),
+            (0, 1),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+        ];
+        const HEX_EVEN: [(isize, isize); 6] = [(-1, -1), (-1, 0), (0, -1), (0, 1), (1, -1), (1, 0)];
+        const HEX_ODD: [(isize, isize); 6] = [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0), (1, 1)];
+        const KNIGHT: [(isize, isize); 8] = [
+            (-2, -1),
+            (-2, 1),
+            (-1, -2),
+            (-1, 2),
+            (1, -2),
+            (1, 2),
+            (2, -1),
+            (2, 1),
+        ];
+        const RADIUS2: [(isize, isize); 24] = [
+            (-2, -2),
+            (-2, -1),
+            (-2, 0),
+            (-2, 1),
+            (-2, 2),
+            (-1, -2),
+            (-1, -1),
+            (-1, 0),
+            (-1, 1),
+            (-1, 2),
+            (0, -2),
+            (0, -1),
+            (0, 1),
+            (0, 2),
+            (1, -2),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+            (1, 2),
+            (2, -2),
+            (2, -1),
+            (2, 0),
+            (2, 1),
+            (2, 2),
+        ];
+        match self {
+            Self::Square => &SQUARE,
+            Self::Knight => &KNIGHT,
+            Self::Radius2 => &RADIUS2,
+            Self::Hex if row % 2 == 1 => &HEX_ODD,
+            Self::Hex => &HEX_EVEN,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Difficulty {
     Beginner,
     Intermediate,
@@ -19,7 +19,7 @@ use super::*;
 
 #[derive(Clone)]
 pub struct Evaluation {
-    count: R64,
+    count: Real,
     spf: PF,
     ipf: Vec<(Index, PF)>, // @todo one-indexed
 }
@@ -37,7 +37,7 @@ impl fmt::Debug for Evaluation {
 
 impl Evaluation {
     pub fn new(state: &MinesweeperState, remainder: BitVec) -> Self {
-        let count = R64::new(1.0);
+        let count = Real::one();
         let flags = remainder
             .iter_ones()
             .filter(|&idx| matches!(state.get(idx), Status::Flagged))
@@ -57,20 +57,27 @@ impl Evaluation {
         Self { count, spf, ipf }
     }
 
-    pub fn label(&self, state: &mut MinesweeperState) -> bool {
-        self.ipf.iter().all(|(idx, pf)| pf.label(state, *idx))
+    // `None` if the number does not fit in a float without the `exact`
+    // feature
+    pub fn configurations(&self, flags: usize, n: usize) -> Option<Real> {
+        let mut weighted_spf = self.spf.clone();
+        let ways = weighted_spf.weighted(flags, n)?;
+        let factors = [weighted_spf.sum(), self.count.clone(), ways];
+        let product = factors.iter().map(util::to_f64).product::<f64>();
+        let fits = cfg!(feature = "exact") || product.is_finite();
+        fits.then(|| factors.iter().fold(Real::one(), |acc, x| acc * x))
     }
 
     pub fn to_probabilities(
         &self,
         flags: usize,
         n: usize,
-    ) -> (Option<R64>, impl Iterator<Item = ScoredIndex> + '_) {
+    ) -> (Option<Real>, impl Iterator<Item = ScoredIndex> + '_) {
         let mut weighted_spf = self.spf.clone();
         weighted_spf.weighted(flags, n);
+        weighted_spf.normalize();
         log::debug!("{:?}", weighted_spf);
-        let bp =
-            (n != 0).then(|| (R64::new(flags as f64) - *&weighted_spf.ev()) / R64::new(n as f64));
+        let bp = (n != 0).then(|| (util::real(flags) - weighted_spf.ev()) / util::real(n));
         let ps = self.ipf.iter().map(move |(idx, pf)| {
             let p = (&weighted_spf * pf).sum();
             (p, *idx)
@@ -82,24 +89,26 @@ impl Evaluation {
 impl Add for Evaluation {
     type Output = Self;
     fn add(self, rhs: Self) -> Self {
-        let count = self.count + rhs.count;
-        let p = self.count / count;
-        let q = rhs.count / count;
+        let count = self.count.clone() + &rhs.count;
+        let p = self.count / &count;
+        let q = rhs.count / &count;
         let g = |x: PF, y: PF| {
             x.zip_with_longest(&y, |either| match either {
-                EitherOrBoth::Both(&c, &d) => c * p + d * q,
-                EitherOrBoth::Left(&c) => c * p,
-                EitherOrBoth::Right(&d) => d * q,
+                EitherOrBoth::Both(c, d) => c.clone() * &p + d.clone() * &q,
+                EitherOrBoth::Left(c) => c.clone() * &p,
+                EitherOrBoth::Right(d) => d.clone() * &q,
             })
         };
-        let spf = g(self.spf, rhs.spf);
+        let (lhs_spf, rhs_spf) = (self.spf, rhs.spf);
+        let spf = g(lhs_spf.clone(), rhs_spf.clone());
+        // Conditional distributions must be mixed as joint distributions
         let ipf = self
             .ipf
             .into_iter()
             .zip(rhs.ipf.into_iter())
             .map(|((i, x), (j, y))| {
                 debug_assert_eq!(i, j);
-                (i, g(x, y))
+                (i, &g(&x * &lhs_spf, &y * &rhs_spf) / &spf)
             })
             .collect();
         Self { count, spf, ipf }
@@ -109,7 +118,7 @@ impl Add for Evaluation {
 impl Mul for Evaluation {
     type Output = Self;
     fn mul(self, rhs: Self) -> Self {
-        let count = self.count * rhs.count;
+        let count = self.count.clone() * &rhs.count;
         let spf = self.spf.convolve(&rhs.spf);
         let lhs_ipf = self
             .ipf
@@ -0,0 +1,279 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+/// A game of Minesweeper as a player sees it. Flags are taken at face
+/// value, right or wrong, and revealing a mine ends the game.
+#[derive(Clone, Debug)]
+pub struct Game {
+    bombs: Vec<bool>,
+    config: Config,
+    state: MinesweeperState,
+    status: GameStatus,
+    moves: usize,
+    // Places the mines at the first reveal
+    generator: Option<Generator>,
+}
+
+impl Game {
+    pub fn new(config: Config) -> Self {
+        Self::with_generator(Generator::new(config))
+    }
+
+    /// A game whose mines are placed by `generator` when the first cell is
+    /// revealed, so that its first click policy can be honoured.
+    pub fn with_generator(generator: Generator) -> Self {
+        let config = generator.config().clone();
+        Self {
+            bombs: vec![false; config.size()],
+            state: MinesweeperState::new(&config),
+            status: Self::start_status(&config),
+            config,
+            moves: 0,
+            generator: Some(generator),
+        }
+    }
+
+    /// A game with the mines at the cells set in `bombs`, which must hold
+    /// exactly `config.mines()` of them, none on a masked cell.
+    pub fn with_bombs(config: Config, bombs: Vec<bool>) -> MsResult<Self> {
+        if !config.is_layout(&bombs) {
+            return Err(MinesweeperError::NumberOfMinesOutOfRange);
+        }
+        Ok(Self {
+            bombs,
+            state: MinesweeperState::new(&config),
+            status: Self::start_status(&config),
+            config,
+            moves: 0,
+            generator: None,
+        })
+    }
+
+    // Won already if every cell is a mine, as nothing is left to reveal
+    fn start_status(config: &Config) -> GameStatus {
+        if config.mines() == config.cells() {
+            GameStatus::Won
+        } else {
+            GameStatus::Playing
+        }
+    }
+
+    #[inline]
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    #[inline]
+    pub fn is_over(&self) -> bool {
+        self.status != GameStatus::Playing
+    }
+
+    /// Number of reveals, flags and unflags that changed the board.
+    #[inline]
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// Flagged cells that are not mines. Empty until the mines are placed.
+    pub fn wrong_flags(&self) -> impl Iterator<Item = Index> + '_ {
+        self.state
+            .board()
+            .iter()
+            .enumerate()
+            .filter(move |&(idx, status)| {
+                status == &Status::Flagged && self.generator.is_none() && !self.bombs[idx]
+            })
+            .map(|(idx, _)| idx)
+    }
+
+    /// The mines, if they have been placed.
+    pub(crate) fn placed_bombs(&self) -> Option<&[bool]> {
+        self.generator.is_none().then(|| &self.bombs[..])
+    }
+
+    /// Applies `action` through the matching `Minesweeper` method, or
+    /// `question`.
+    pub fn apply(&mut self, action: Action) -> MsResult<()> {
+        match action {
+            Action::Reveal(idx) => self.reveal(idx),
+            Action::Flag(idx) => self.flag(idx),
+            Action::Unflag(idx) => self.unflag(idx),
+            Action::Question(idx) => self.question(idx),
+            Action::Chord(idx) => self.chord(idx),
+        }
+    }
+
+    /// Puts a question mark on an unknown cell, or takes it off.
+    pub fn question(&mut self, idx: Index) -> MsResult<()> {
+        self.check(idx)?;
+        match self.state.get(idx) {
+            Status::Unknown => self.state.set_question(idx),
+            Status::Question => self.state.unset_question(idx),
+            _ => return Err(MinesweeperError::InvalidMove(idx)),
+        }
+        self.moves += 1;
+        Ok(())
+    }
+
+    // Puts back the given cells, status and move count, as recorded by a
+    // `Session`
+    pub(crate) fn restore(
+        &mut self,
+        cells: impl Iterator<Item = (Index, Status)>,
+        status: GameStatus,
+        moves: usize,
+    ) {
+        for (idx, cell) in cells {
+            self.state.set_status(idx, cell);
+        }
+        self.status = status;
+        self.moves = moves;
+    }
+
+    // Places the mines around a first reveal at `first`, unless they are
+    // placed already
+    pub(crate) fn place(&mut self, first: Index) {
+        if let Some(generator) = self.generator.take() {
+            self.bombs = generator.generate(first);
+        }
+    }
+
+    // The generator still to place the mines, if they are not placed
+    pub(crate) fn generator(&self) -> Option<&Generator> {
+        self.generator.as_ref()
+    }
+
+    // Takes back the placing of the mines, as recorded by a `Session`
+    pub(crate) fn unplace(&mut self, generator: Generator) {
+        self.generator = Some(generator);
+    }
+
+    fn check(&self, idx: Index) -> MsResult<()> {
+        if self.is_over() {
+            Err(MinesweeperError::GameOver)
+        } else if idx >= self.config.size() {
+            Err(MinesweeperError::InvalidMove(idx))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Minesweeper for Game {
+    /// The mines are only shown once the game is over.
+    fn get_bombs(&self) -> Option<&[bool]> {
+        self.is_over().then(|| &self.bombs[..])
+    }
+
+    fn get_config(&self) -> &Config {
+        &self.config
+    }
+
+    fn get_state(&self) -> &MinesweeperState {
+        &self.state
+    }
+
+    fn get_status(&self) -> GameStatus {
+        self.status
+    }
+
+    fn pull(&self) -> MsResult<MinesweeperState> {
+        Ok(self.state.clone())
+    }
+
+    fn flag(&mut self, idx: usize) -> MsResult<()> {
+        self.check(idx)?;
+        let flaggable = matches!(self.state.get(idx), Status::Question | Status::Unknown);
+        if !flaggable || !self.state.set_flag(idx) {
+            return Err(MinesweeperError::InvalidMove(idx));
+        }
+        self.moves += 1;
+        Ok(())
+    }
+
+    fn unflag(&mut self, idx: usize) -> MsResult<()> {
+        self.check(idx)?;
+        if self.state.get(idx) != Status::Flagged {
+            return Err(MinesweeperError::InvalidMove(idx));
+        }
+        self.state.unset_flag(idx);
+        self.moves += 1;
+        Ok(())
+    }
+
+    /// Revealing a revealed cell does nothing; revealing a flagged cell is
+    /// refused.
+    fn reveal(&mut self, idx: usize) -> MsResult<()> {
+        self.check(idx)?;
+        match self.state.get(idx) {
+            Status::Known(_) => return Ok(()),
+            Status::Flagged | Status::Masked => return Err(MinesweeperError::InvalidMove(idx)),
+            Status::Marked | Status::Question | Status::Unknown => (),
+        }
+        self.moves += 1;
+        self.place(idx);
+        if self.bombs[idx] {
+            self.status = GameStatus::Lost(idx);
+            return Ok(());
+        }
+        self.state.reveal(idx, &self.bombs, &self.config);
+        if self.state.knowns() + self.config.mines() == self.config.cells() {
+            self.status = GameStatus::Won;
+        }
+        Ok(())
+    }
+
+    /// Reveals the neighbours of a revealed cell whose count is met by its
+    /// flags. A wrong flag among them loses the game at the first mine.
+    fn chord(&mut self, idx: usize) -> MsResult<()> {
+        self.check(idx)?;
+        let cells = self
+            .state
+            .chord_cells(idx, &self.config)
+            .ok_or(MinesweeperError::InvalidMove(idx))?;
+        if cells.is_empty() {
+            return Ok(());
+        }
+        self.moves += 1;
+        let mut lost = None;
+        for cidx in cells {
+            if self.bombs[cidx] {
+                lost = lost.or(Some(cidx));
+            } else if matches!(
+                self.state.get(cidx),
+                Status::Marked | Status::Question | Status::Unknown
+            ) {
+                self.state.reveal(cidx, &self.bombs, &self.config);
+            }
+        }
+        self.status = match lost {
+            Some(cidx) => GameStatus::Lost(cidx),
+            None if self.state.knowns() + self.config.mines() == self.config.cells() => {
+                GameStatus::Won
+            }
+            None => GameStatus::Playing,
+        };
+        Ok(())
+    }
+
+    /// The game keeps only what the player did, not the solver's labels.
+    fn set_internal(&mut self, _: MinesweeperState) -> MsResult<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+/// Places the mines of a board from the seed of its `Config`.
+#[derive(Clone, Debug)]
+pub struct Generator {
+    config: Config,
+    first_click: FirstClick,
+}
+
+impl Generator {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            first_click: FirstClick::default(),
+        }
+    }
+
+    pub fn with_first_click(mut self, first_click: FirstClick) -> Self {
+        self.first_click = first_click;
+        self
+    }
+
+    #[inline]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    #[inline]
+    pub fn first_click(&self) -> FirstClick {
+        self.first_click
+    }
+
+    /// The mines of a board whose first reveal is at `first`. If the board
+    /// is too full to keep the guaranteed cells free, as many of them as
+    /// possible are kept free, the first cell last.
+    pub fn generate(&self, first: Index) -> Vec<bool> {
+        self.generate_with(first, &mut self.config.new_rng())
+    }
+
+    /// The mines of a board with `first` as opening cell that the solver
+    /// finishes from there using certain deductions only, along with the
+    /// number of candidate boards checked. A board that needs a guess is
+    /// repaired by moving a mine off the stuck frontier, or re-rolled once
+    /// no mine can be moved. Fails after `max_attempts` candidates.
+    pub fn generate_no_guess(
+        &self,
+        first: Index,
+        max_attempts: usize,
+    ) -> MsResult<(Vec<bool>, usize)> {
+        let solver = Solver::new(self.config.clone());
+        let mut rng = self.config.new_rng();
+        let mut bombs = self.generate_with(first, &mut rng);
+        for attempt in 1..=max_attempts {
+            let stuck = match solver.finish(&bombs, first) {
+                Some(stuck) => stuck,
+                None => return Ok((bombs, attempt)),
+            };
+            log::debug!("Attempt {} needs a guess", attempt);
+            if !self.repair(&solver, &stuck, &mut bombs, &mut rng) {
+                bombs = self.generate_with(first, &mut rng);
+            }
+        }
+        Err(MinesweeperError::GenerationFailed)
+    }
+
+    // Moves a mine next to the revealed cells of `stuck` to an unknown cell
+    // away from them. False if there is no such pair of cells.
+    fn repair(
+        &self,
+        solver: &Solver,
+        stuck: &MinesweeperState,
+        bombs: &mut [bool],
+        rng: &mut impl Rng,
+    ) -> bool {
+        let (frontier, inside) = (0..self.config.size())
+            .filter(|&idx| stuck.get(idx) == Status::Unknown)
+            .partition::<Vec<Index>, _>(|&idx| {
+                solver
+                    .square(idx)
+                    .iter()
+                    .any(|&cidx| stuck.get_known(cidx).is_some())
+            });
+        let from = frontier.iter().filter(|&&idx| bombs[idx]).choose(rng);
+        let to = inside.iter().filter(|&&idx| !bombs[idx]).choose(rng);
+        match (from, to) {
+            (Some(&from), Some(&to)) => {
+                bombs[from] = false;
+                bombs[to] = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn generate_with(&self, first: Index, rng: &mut impl Rng) -> Vec<bool> {
+        let config = &self.config;
+        let mut excluded = (0..config.size())
+            .map(|idx| config.is_masked(idx))
+            .collect::<Vec<bool>>();
+        match self.first_click {
+            FirstClick::Opening
+                if config.cells() - config.mines() > config.square(first).count() =>
+            {
+                excluded[first] = true;
+                for cidx in config.square(first) {
+                    excluded[cidx] = true;
+                }
+            }
+            FirstClick::Safe | FirstClick::Opening if config.cells() > config.mines() => {
+                excluded[first] = true;
+            }
+            _ => (),
+        }
+
+        let mut cells = (0..config.size())
+            .filter(|&idx| !excluded[idx])
+            .collect::<Vec<Index>>();
+        let (mines, _) = cells.partial_shuffle(rng, config.mines());
+        let mut bombs = vec![false; config.size()];
+        for &idx in mines.iter() {
+            bombs[idx] = true;
+        }
+
+        if self.first_click == FirstClick::Relocate && bombs[first] {
+            let free = (0..config.size()).find(|&idx| !bombs[idx] && !config.is_masked(idx));
+            if let Some(free) = free {
+                bombs[first] = false;
+                bombs[free] = true;
+            }
+        }
+        bombs
+    }
+}
+
+impl Solver {
+    // Plays the board with mines at `bombs` from `first`, revealing only
+    // cells certain to be safe. Returns the state it gets stuck in, if any.
+    fn finish(&self, bombs: &[bool], first: Index) -> Option<MinesweeperState> {
+        let config = self.config();
+        let mut state = MinesweeperState::new(config);
+        if bombs[first] {
+            return Some(state);
+        }
+        state.reveal(first, bombs, config);
+        while state.knowns() + config.mines() < config.cells() {
+            let consistent = self.make_consistent_all(&mut state)
+                && self.pattern_deduction(&mut state)
+                && self.linear_deduction(&mut state);
+            debug_assert!(consistent);
+            if !state.board().contains(&Status::Marked) {
+                // Count the frontier only once the cheaper passes are stuck
+                let mut counted = state.clone();
+                if self
+                    .evaluate(&mut counted)
+                    .is_none_or(|(_, exact, _)| !exact)
+                {
+                    return Some(state);
+                }
+                state = counted;
+            }
+            let marked = (0..config.size())
+                .filter(|&idx| state.get(idx) == Status::Marked)
+                .collect::<Vec<Index>>();
+            if marked.is_empty() || marked.iter().any(|&idx| bombs[idx]) {
+                return Some(state);
+            }
+            for idx in marked {
+                if state.get(idx) == Status::Marked {
+                    state.reveal(idx, bombs, config);
+                }
+            }
+        }
+        None
+    }
+}
@@ -61,9 +61,9 @@ impl<'a> Group<'a> {
         let mut group = Group::from(solver);
         for (idx, status) in state.board().iter().enumerate() {
             match status {
-                Status::Flagged | Status::Marked | Status::Known(0) => continue,
+                Status::Flagged | Status::Marked | Status::Known(0) | Status::Masked => continue,
                 Status::Known(_) => group.knowns.set(idx, true),
-                Status::Unknown => group.unknowns.set(idx, true),
+                Status::Question | Status::Unknown => group.unknowns.set(idx, true),
             }
         }
         group.trim(state)
@@ -107,6 +107,19 @@ impl<'a> Group<'a> {
         }
     }
 
+    pub fn knowns(&self) -> impl Iterator<Item = Index> + '_ {
+        self.knowns.iter_ones()
+    }
+
+    pub fn unknowns(&self) -> impl Iterator<Item = Index> + '_ {
+        self.unknowns.iter_ones()
+    }
+
+    #[inline]
+    pub fn contains(&self, idx: Index) -> bool {
+        self.knowns[idx] || self.unknowns[idx]
+    }
+
     pub fn get(&self) -> Option<Index> {
         self.unknowns.iter_ones().max_by_key(|&idx| {
             self.solver
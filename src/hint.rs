@@ -0,0 +1,245 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+// Largest number of unknown cells enumerated when minimising a justification
+const MAX_VARIABLES: usize = 20;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Reason {
+    /// The counts of these cells alone force the action. No proper subset does.
+    Constraints(Vec<Index>),
+    /// The action is forced only by counting every layout of the frontier
+    /// component with these cells, together with the total number of mines.
+    Component(Vec<Index>),
+    /// No certain action exists. The probability that the cell is a mine was
+    /// computed over the frontier component with these cells.
+    Probability(Real, Vec<Index>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hint {
+    action: Action,
+    reason: Reason,
+}
+
+impl Hint {
+    #[inline]
+    pub fn action(&self) -> Action {
+        self.action
+    }
+
+    #[inline]
+    pub fn reason(&self) -> &Reason {
+        &self.reason
+    }
+
+    #[inline]
+    pub fn is_certain(&self) -> bool {
+        !matches!(self.reason, Reason::Probability(..))
+    }
+}
+
+impl Solver {
+    /// Returns the next move for `state` with the reasoning behind it,
+    /// preferring the certain move with the smallest justification.
+    pub fn hint(&self, state: &MinesweeperState) -> MsResult<Option<Hint>> {
+        let mut state = state.clone();
+        state.clear_marks();
+        state.clear_questions();
+        let mut deduced = state.clone();
+        let (_, _, v) = self
+            .make_consistent_all(&mut deduced)
+            .then(|| self.evaluate(&mut deduced))
+            .flatten()
+            .ok_or(MinesweeperError::InconsistentState)?;
+
+        let certain = (0..self.size())
+            .filter_map(|idx| match (state.get(idx), deduced.get(idx)) {
+                (Status::Unknown, Status::Flagged) => Some(Action::Flag(idx)),
+                (Status::Unknown, Status::Marked) => Some(Action::Reveal(idx)),
+                _ => None,
+            })
+            .map(|action| self.justify(&state, action))
+            .min_by_key(|hint| match &hint.reason {
+                Reason::Constraints(v) => (0, v.len(), hint.action.is_flag()),
+                Reason::Component(v) => (1, v.len(), hint.action.is_flag()),
+                Reason::Probability(..) => unreachable!(),
+            });
+        if certain.is_some() {
+            return Ok(certain);
+        }
+
+        Ok(self.guess(&deduced, &v).map(|(p, idx)| Hint {
+            action: Action::Reveal(idx),
+            reason: Reason::Probability(p, self.component(&state, idx)),
+        }))
+    }
+
+    fn component(&self, state: &MinesweeperState, idx: Index) -> Vec<Index> {
+        let (group, _) = Group::new(self, state);
+        group
+            .into_iter()
+            .flatten()
+            .find(|split| split.contains(idx))
+            .map(|split| split.knowns().collect())
+            .unwrap_or_default()
+    }
+
+    fn justify(&self, state: &MinesweeperState, action: Action) -> Hint {
+        let idx = action.index();
+        let is_mine = action.is_flag();
+        let forces = |constraints: &[Index]| self.forces(state, constraints, idx) == Some(is_mine);
+
+        // Grow outwards from `idx` until the constraints force the action
+        let mut constraints = Vec::new();
+        let mut frontier = vec![idx];
+        let mut seen = vec![false; self.size()];
+        seen[idx] = true;
+        while !forces(&constraints) {
+            let next = frontier
+                .iter()
+                .flat_map(|&cidx| self.square(cidx).iter().copied())
+                .filter(|&cidx| state.get_known(cidx).is_some())
+                .unique()
+                .filter(|&cidx| !std::mem::replace(&mut seen[cidx], true))
+                .collect::<Vec<Index>>();
+            if next.is_empty() || self.variables(state, &constraints).len() > MAX_VARIABLES {
+                return Hint {
+                    action,
+                    reason: Reason::Component(self.component(state, idx)),
+                };
+            }
+            frontier = next
+                .iter()
+                .flat_map(|&cidx| self.square(cidx).iter().copied())
+                .filter(|&cidx| state.get(cidx) == Status::Unknown)
+                .filter(|&cidx| !std::mem::replace(&mut seen[cidx], true))
+                .collect();
+            constraints.extend(next);
+        }
+
+        // Then drop every constraint that is not needed
+        let mut i = constraints.len();
+        while i > 0 {
+            i -= 1;
+            let cidx = constraints.remove(i);
+            if !forces(&constraints) {
+                constraints.insert(i, cidx);
+            }
+        }
+        constraints.sort_unstable();
+        Hint {
+            action,
+            reason: Reason::Constraints(constraints),
+        }
+    }
+
+    fn variables(&self, state: &MinesweeperState, constraints: &[Index]) -> Vec<Index> {
+        constraints
+            .iter()
+            .flat_map(|&cidx| self.square(cidx).iter().copied())
+            .filter(|&cidx| state.get(cidx) == Status::Unknown)
+            .unique()
+            .collect()
+    }
+
+    // Whether `idx` is a mine in every assignment of the unknown cells around
+    // `constraints` that satisfies their counts, ignoring the rest of the board.
+    // `None` if `idx` takes both values, or if the counts cannot be satisfied.
+    fn forces(&self, state: &MinesweeperState, constraints: &[Index], idx: Index) -> Option<bool> {
+        let vars = self.variables(state, constraints);
+        let target = vars.iter().position(|&cidx| cidx == idx)?;
+        if vars.len() > MAX_VARIABLES {
+            return None;
+        }
+        let rows = constraints
+            .iter()
+            .map(|&cidx| {
+                let flags = self
+                    .square(cidx)
+                    .iter()
+                    .filter(|&&x| state.get(x) == Status::Flagged)
+                    .count();
+                let count = state.get_known(cidx).unwrap().checked_sub(flags)?;
+                let cols = self
+                    .square(cidx)
+                    .iter()
+                    .filter_map(|x| vars.iter().position(|y| x == y))
+                    .collect::<Square>();
+                Some((count, cols))
+            })
+            .collect::<Option<Vec<(usize, Square)>>>()?;
+
+        // (mines placed, cells left) per row, updated as variables are assigned
+        let mut partial = rows
+            .iter()
+            .map(|(_, cols)| (0, cols.len()))
+            .collect::<Vec<(usize, usize)>>();
+        let mut seen = [false; 2];
+        enumerate(
+            &rows,
+            &mut partial,
+            &mut vec![false; vars.len()],
+            0,
+            target,
+            &mut seen,
+        );
+        match seen {
+            [true, true] | [false, false] => None,
+            [_, is_mine] => Some(is_mine),
+        }
+    }
+}
+
+fn enumerate(
+    rows: &[(usize, Square)],
+    partial: &mut [(usize, usize)],
+    assignment: &mut [bool],
+    var: usize,
+    target: usize,
+    seen: &mut [bool; 2],
+) {
+    if seen[0] && seen[1] {
+        return;
+    }
+    if var == assignment.len() {
+        seen[assignment[target] as usize] = true;
+        return;
+    }
+    for value in [false, true] {
+        assignment[var] = value;
+        let mut ok = true;
+        for ((count, cols), (mines, left)) in rows.iter().zip(partial.iter_mut()) {
+            if cols.contains(&var) {
+                *mines += value as usize;
+                *left -= 1;
+                ok &= *mines <= *count && *count <= *mines + *left;
+            }
+        }
+        if ok {
+            enumerate(rows, partial, assignment, var + 1, target, seen);
+        }
+        for ((_, cols), (mines, left)) in rows.iter().zip(partial.iter_mut()) {
+            if cols.contains(&var) {
+                *mines -= value as usize;
+                *left += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,368 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+// Width and height of a cell in the recorded mouse coordinates
+const CELL_PIXELS: usize = 16;
+
+// Offset of the board in the mouse coordinates of `.rmv` files
+const RMV_BOARD_X: usize = 12;
+const RMV_BOARD_Y: usize = 56;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Button {
+    Move,
+    LeftDown,
+    LeftUp,
+    RightDown,
+    RightUp,
+    MiddleDown,
+    MiddleUp,
+}
+
+// A recorded mouse event, with the cell under the pointer
+#[derive(Clone, Copy, Debug)]
+struct Event {
+    time: Duration,
+    button: Button,
+    cell: Option<Index>,
+}
+
+// Big-endian reader whose errors hold the offset they occurred at
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn error(&self) -> MinesweeperError {
+        MinesweeperError::ParseError(self.offset)
+    }
+
+    fn bytes(&mut self, n: usize) -> MsResult<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + n)
+            .ok_or_else(|| self.error())?;
+        self.offset += n;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, n: usize) -> MsResult<()> {
+        self.bytes(n).map(|_| ())
+    }
+
+    fn uint(&mut self, n: usize) -> MsResult<usize> {
+        let bytes = self.bytes(n)?;
+        Ok(bytes.iter().fold(0, |acc, &b| acc << 8 | b as usize))
+    }
+
+    fn u8(&mut self) -> MsResult<usize> {
+        self.uint(1)
+    }
+
+    fn u16(&mut self) -> MsResult<usize> {
+        self.uint(2)
+    }
+
+    fn u24(&mut self) -> MsResult<usize> {
+        self.uint(3)
+    }
+
+    fn u32(&mut self) -> MsResult<usize> {
+        self.uint(4)
+    }
+}
+
+fn cell_at(config: &Config, x: usize, y: usize) -> Option<Index> {
+    let (row, col) = (y / CELL_PIXELS, x / CELL_PIXELS);
+    (row < config.width() && col < config.length()).then(|| config.from_rc(row, col))
+}
+
+// The event stored in the eight bytes at `at` of an `.avf` file. Times are
+// stored as seconds plus one and hundredths.
+fn avf_event(data: &[u8], at: usize, config: &Config) -> Option<Event> {
+    let b = data.get(at..at + 8)?;
+    let button = match b[0] {
+        1 | 11 => Button::Move,
+        3 => Button::LeftDown,
+        5 | 21 => Button::LeftUp,
+        9 => Button::RightDown,
+        17 | 145 => Button::RightUp,
+        33 => Button::MiddleDown,
+        65 | 193 => Button::MiddleUp,
+        _ => return None,
+    };
+    let secs = (b[6] as u64 * 256 + b[2] as u64).checked_sub(1)?;
+    if b[4] >= 100 {
+        return None;
+    }
+    let time = Duration::from_millis(secs * 1000 + b[4] as u64 * 10);
+    let x = b[1] as usize * 256 + b[3] as usize;
+    let y = b[5] as usize * 256 + b[7] as usize;
+    Some(Event {
+        time,
+        button,
+        cell: cell_at(config, x, y),
+    })
+}
+
+// Reads `config.mines()` mine positions, given as row and column pairs, or
+// column and row pairs if `swap`, counting from `base`
+fn read_mines(
+    reader: &mut Reader,
+    config: &Config,
+    base: usize,
+    swap: bool,
+) -> MsResult<Vec<bool>> {
+    let mut bombs = vec![false; config.size()];
+    for _ in 0..config.mines() {
+        let (a, b) = (reader.u8()?, reader.u8()?);
+        let (row, col) = if swap { (b, a) } else { (a, b) };
+        let (row, col) = match (row.checked_sub(base), col.checked_sub(base)) {
+            (Some(row), Some(col)) if row < config.width() && col < config.length() => (row, col),
+            _ => return Err(reader.error()),
+        };
+        bombs[config.from_rc(row, col)] = true;
+    }
+    Ok(bombs)
+}
+
+impl Replay {
+    /// Reads a Minesweeper Arbiter video (`.avf`).
+    ///
+    /// The file starts with a version byte, four reserved bytes and the
+    /// level: 3, 4 and 5 for the standard boards, or 6 followed by the
+    /// columns and rows less one and a two byte mine count. The mines follow
+    /// as one-based row and column pairs. After a text header, every event
+    /// takes eight bytes: the button, then the interleaved bytes of the
+    /// time and of the pointer position.
+    ///
+    /// Mouse events are turned into actions as Arbiter plays them; see
+    /// `from_rmv`. Imported replays have seed 0. Parse errors hold the byte
+    /// offset.
+    pub fn from_avf(data: &[u8]) -> MsResult<Self> {
+        let mut reader = Reader::new(data);
+        reader.skip(5)?;
+        let (rows, cols, mines) = match reader.u8()? {
+            3 => (8, 8, 10),
+            4 => (16, 16, 40),
+            5 => (16, 30, 99),
+            6 => {
+                let cols = reader.u8()? + 1;
+                let rows = reader.u8()? + 1;
+                (rows, cols, reader.u16()?)
+            }
+            _ => return Err(MinesweeperError::ParseError(reader.offset - 1)),
+        };
+        let config = Config::new(rows, cols, mines, Some(0)).map_err(|_| reader.error())?;
+        let bombs = read_mines(&mut reader, &config, 1, false)?;
+
+        // The header has no stated length, so the events start at the first
+        // two events on the board in order
+        let start = (reader.offset..data.len())
+            .find(|&at| {
+                match (
+                    avf_event(data, at, &config),
+                    avf_event(data, at + 8, &config),
+                ) {
+                    (Some(a), Some(b)) => a.cell.is_some() && b.cell.is_some() && a.time <= b.time,
+                    _ => false,
+                }
+            })
+            .ok_or_else(|| reader.error())?;
+        let mut events = Vec::<Event>::new();
+        let mut at = start;
+        while let Some(event) = avf_event(data, at, &config) {
+            if events.last().is_some_and(|last| last.time > event.time) {
+                break;
+            }
+            events.push(event);
+            at += 8;
+        }
+        Self::from_events(config, bombs, &[], &events).map_err(|_| reader.error())
+    }
+
+    /// Reads a Minesweeper Clone video (`.rmv`).
+    ///
+    /// After the `*rmv` magic and a format number of 1, the header gives
+    /// the sizes of the sections that follow: the result string, version
+    /// and player info, the board, the cells flagged before the start, the
+    /// properties, the events and a checksum. The board holds the columns,
+    /// rows, a two byte mine count and zero-based column and row pairs.
+    /// Mouse events hold a three byte time in milliseconds and the pointer
+    /// position in window coordinates; other events are skipped up to the
+    /// end of the game.
+    ///
+    /// Mouse events are turned into actions as the clone plays them: a left
+    /// release reveals, a right press toggles a flag, and a middle release,
+    /// or releasing one button while both are held, chords. Actions that
+    /// change nothing in the game, or are not allowed, are dropped.
+    /// Imported replays have seed 0. Parse errors hold the byte offset.
+    pub fn from_rmv(data: &[u8]) -> MsResult<Self> {
+        let mut reader = Reader::new(data);
+        if reader.bytes(4)? != b"*rmv" {
+            return Err(MinesweeperError::ParseError(0));
+        }
+        if reader.u16()? != 1 {
+            return Err(MinesweeperError::ParseError(4));
+        }
+        let result = reader.u32()?;
+        let version = reader.u16()?;
+        let player = reader.u16()?;
+        let board = reader.u16()?;
+        let preflags = reader.u16()?;
+        let properties = reader.u16()?;
+        reader.skip(6)?;
+        reader.skip(result + version + player)?;
+
+        let end = reader.offset + board;
+        let cols = reader.u8()?;
+        let rows = reader.u8()?;
+        let mines = reader.u16()?;
+        let config = Config::new(rows, cols, mines, Some(0)).map_err(|_| reader.error())?;
+        let bombs = read_mines(&mut reader, &config, 0, true)?;
+        reader.offset = end;
+
+        let mut flags = Vec::new();
+        if preflags > 0 {
+            let end = reader.offset + preflags;
+            let count = reader.u16()?;
+            for _ in 0..count {
+                let (col, row) = (reader.u8()?, reader.u8()?);
+                if row >= config.width() || col >= config.length() {
+                    return Err(reader.error());
+                }
+                flags.push(config.from_rc(row, col));
+            }
+            reader.offset = end;
+        }
+        reader.skip(properties)?;
+
+        let mut events = Vec::new();
+        loop {
+            let at = reader.offset;
+            let button = match reader.u8()? {
+                0 => {
+                    reader.skip(4)?;
+                    continue;
+                }
+                1 => Button::Move,
+                2 => Button::LeftDown,
+                3 => Button::LeftUp,
+                4 => Button::RightDown,
+                5 => Button::RightUp,
+                6 => Button::MiddleDown,
+                7 => Button::MiddleUp,
+                8..=14 | 18..=27 => {
+                    reader.skip(2)?;
+                    continue;
+                }
+                15..=17 => break,
+                _ => return Err(MinesweeperError::ParseError(at)),
+            };
+            let time = Duration::from_millis(reader.u24()? as u64);
+            let (x, y) = (reader.u16()?, reader.u16()?);
+            let cell = match (x.checked_sub(RMV_BOARD_X), y.checked_sub(RMV_BOARD_Y)) {
+                (Some(x), Some(y)) => cell_at(&config, x, y),
+                _ => None,
+            };
+            events.push(Event { time, button, cell });
+        }
+        Self::from_events(config, bombs, &flags, &events).map_err(|_| reader.error())
+    }
+
+    // Plays `events` on a game with `bombs` and `flags` already flagged,
+    // keeping the actions that changed it
+    fn from_events(
+        config: Config,
+        bombs: Vec<bool>,
+        flags: &[Index],
+        events: &[Event],
+    ) -> MsResult<Self> {
+        let mut game = Game::with_bombs(config.clone(), bombs.clone())?;
+        let mut actions = Vec::new();
+        let mut record = |game: &mut Game, time: Duration, action: Action| {
+            let moves = game.moves();
+            if game.apply(action).is_ok() && game.moves() != moves {
+                actions.push((time, action));
+            }
+        };
+        for &idx in flags {
+            record(&mut game, Duration::ZERO, Action::Flag(idx));
+        }
+
+        let (mut left, mut right) = (false, false);
+        // Both buttons have been held since they were last both up, and
+        // whether one has been released since, which chords
+        let (mut both, mut chorded) = (false, false);
+        for event in events {
+            if game.is_over() {
+                break;
+            }
+            let time = event.time;
+            let action = match (event.button, event.cell) {
+                (Button::LeftDown, _) => {
+                    left = true;
+                    both |= right;
+                    None
+                }
+                (Button::RightDown, cell) => {
+                    right = true;
+                    both |= left;
+                    match cell {
+                        Some(idx) if !left && game.get_state().get(idx) == Status::Flagged => {
+                            Some(Action::Unflag(idx))
+                        }
+                        Some(idx) if !left => Some(Action::Flag(idx)),
+                        _ => None,
+                    }
+                }
+                (Button::LeftUp, cell) | (Button::RightUp, cell) => {
+                    let was_left = event.button == Button::LeftUp;
+                    if was_left {
+                        left = false;
+                    } else {
+                        right = false;
+                    }
+                    let action = match cell {
+                        Some(idx) if both && !chorded => Some(Action::Chord(idx)),
+                        Some(idx) if !both && was_left => Some(Action::Reveal(idx)),
+                        _ => None,
+                    };
+                    chorded |= both;
+                    if !left && !right {
+                        both = false;
+                        chorded = false;
+                    }
+                    action
+                }
+                (Button::MiddleUp, Some(idx)) => Some(Action::Chord(idx)),
+                (Button::Move, _) | (Button::MiddleDown, _) | (Button::MiddleUp, None) => None,
+            };
+            if let Some(action) = action {
+                record(&mut game, time, action);
+            }
+        }
+        Self::new(config, bombs, actions)
+    }
+}
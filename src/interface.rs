@@ -21,8 +21,11 @@ pub trait Minesweeper {
     fn get_bombs(&self) -> Option<&[bool]>;
     fn get_config(&self) -> &Config;
     fn get_state(&self) -> &MinesweeperState;
+    fn get_status(&self) -> GameStatus;
     fn pull(&self) -> MsResult<MinesweeperState>;
     fn flag(&mut self, idx: usize) -> MsResult<()>;
+    fn unflag(&mut self, idx: usize) -> MsResult<()>;
     fn reveal(&mut self, idx: usize) -> MsResult<()>;
+    fn chord(&mut self, idx: usize) -> MsResult<()>;
     fn set_internal(&mut self, state: MinesweeperState) -> MsResult<()>;
 }
@@ -15,6 +15,22 @@
 // You should have received a copy of the GNU General Public License
 // along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
 
+// `Real` is only `Copy` without the `exact` feature
+#![cfg_attr(not(feature = "exact"), allow(clippy::clone_on_copy, clippy::op_ref))]
+
+mod analysis;
+pub use analysis::{Analysis, Certainty, Move};
+
+mod board;
+pub use board::Board;
+
+mod budget;
+pub use budget::Budget;
+use budget::Meter;
+
+mod cache;
+pub use cache::Cache;
+
 mod config;
 pub use config::Config;
 
@@ -24,18 +40,45 @@ pub use enums::*;
 mod eval;
 use eval::Evaluation;
 
+mod game;
+pub use game::Game;
+
+mod generate;
+pub use generate::Generator;
+
 mod group;
 use group::Group;
 
+mod hint;
+pub use hint::{Hint, Reason};
+
+mod import;
+
 mod interface;
 pub use interface::Minesweeper;
 
+mod linalg;
+
+mod lookahead;
+
 mod mock;
 pub use mock::MockMinesweeper;
 
+mod pattern;
+pub use pattern::Deduction;
+
 mod pf;
 use pf::PF;
 
+mod replay;
+pub use replay::{Recorder, Replay, ReplayPlayer};
+
+mod sample;
+pub use sample::{Estimate, Sampler};
+
+mod session;
+pub use session::Session;
+
 mod show;
 pub use show::ShowMinesweeper;
 
@@ -43,26 +86,51 @@ mod solve;
 pub use solve::Solver;
 
 mod state;
-use state::MinesweeperState;
+pub use state::MinesweeperState;
+
+mod strategy;
+pub use strategy::{GuessStrategy, Heuristic, MaxInformation, MaxProgress, MinProbability};
+
+mod topology;
+pub use topology::{Graph, Topology};
 
 mod util;
 
-use arrayvec::ArrayVec;
 use bitvec::prelude::*;
 use itertools::{EitherOrBoth, Itertools};
+#[cfg(not(feature = "exact"))]
 use noisy_float::prelude::*;
+use num_traits::{FromPrimitive, One, ToPrimitive, Zero};
 use rand::{
     self,
     distributions::{Distribution, Uniform},
     rngs::StdRng,
+    seq::{IteratorRandom, SliceRandom},
     Rng, SeedableRng,
 };
-use std::cmp::{max, min};
+use smallvec::SmallVec;
+use std::cell::Cell;
+use std::cmp::{min, Ordering};
+use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
 use std::fmt;
+use std::iter;
 use std::ops::{Add, Mul, Div};
-use strum_macros::EnumString;
+use std::panic::RefUnwindSafe;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use strum_macros::{Display, EnumString};
 use thiserror::Error;
 
+/// Scalar used for model counts and probabilities. With the `exact` feature
+/// this is an arbitrary precision rational, so certainties are decided exactly.
+#[cfg(not(feature = "exact"))]
+pub type Real = R64;
+#[cfg(feature = "exact")]
+pub type Real = num_rational::BigRational;
+
 type Index = usize;
-type ScoredIndex = (R64, usize);
-type Square = ArrayVec<usize, 8>;
+type ScoredIndex = (Real, usize);
+type Square = SmallVec<[usize; 8]>;
@@ -0,0 +1,267 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+// Largest frontier reduced together with the total number of mines. Larger
+// frontiers are reduced one component at a time.
+const MAX_GLOBAL_VARIABLES: usize = 256;
+
+// A linear system `rows * x = rhs` over integers, with `0 <= x[i] <= bounds[i]`
+struct System {
+    rows: Vec<Vec<i64>>,
+    rhs: Vec<i64>,
+    bounds: Vec<i64>,
+}
+
+impl System {
+    fn new(bounds: Vec<i64>) -> Self {
+        Self {
+            rows: Vec::new(),
+            rhs: Vec::new(),
+            bounds,
+        }
+    }
+
+    fn push(&mut self, vars: impl Iterator<Item = usize>, rhs: usize) {
+        let mut row = vec![0; self.bounds.len()];
+        for var in vars {
+            row[var] = 1;
+        }
+        self.rows.push(row);
+        self.rhs.push(rhs as i64);
+    }
+
+    // Brings the system to reduced row echelon form, keeping the entries
+    // integral. `None` if an entry overflows.
+    fn reduce(&mut self) -> Option<()> {
+        let mut pivot_row = 0;
+        for col in 0..self.bounds.len() {
+            let pivot = match (pivot_row..self.rows.len()).find(|&r| self.rows[r][col] != 0) {
+                Some(pivot) => pivot,
+                None => continue,
+            };
+            self.rows.swap(pivot_row, pivot);
+            self.rhs.swap(pivot_row, pivot);
+            for r in 0..self.rows.len() {
+                let factor = self.rows[r][col];
+                if r == pivot_row || factor == 0 {
+                    continue;
+                }
+                let scale = self.rows[pivot_row][col];
+                for c in 0..self.bounds.len() {
+                    self.rows[r][c] = self.rows[r][c]
+                        .checked_mul(scale)?
+                        .checked_sub(self.rows[pivot_row][c].checked_mul(factor)?)?;
+                }
+                self.rhs[r] = self.rhs[r]
+                    .checked_mul(scale)?
+                    .checked_sub(self.rhs[pivot_row].checked_mul(factor)?)?;
+                self.normalize(r);
+            }
+            pivot_row += 1;
+        }
+        Some(())
+    }
+
+    fn normalize(&mut self, r: usize) {
+        let gcd = self.rows[r]
+            .iter()
+            .chain(iter::once(&self.rhs[r]))
+            .fold(0, |acc, &x| util::gcd(acc, x.abs()));
+        if gcd > 1 {
+            self.rows[r].iter_mut().for_each(|x| *x /= gcd);
+            self.rhs[r] /= gcd;
+        }
+    }
+
+    // Values forced by the bounds on some row, or `None` if a row cannot be
+    // satisfied within them
+    fn forced(&self) -> Option<Vec<(usize, i64)>> {
+        let mut forced = Vec::new();
+        for (row, &rhs) in self.rows.iter().zip(self.rhs.iter()) {
+            let (lo, hi) = row
+                .iter()
+                .zip(self.bounds.iter())
+                .fold((0, 0), |(lo, hi), (&a, &u)| match a.cmp(&0) {
+                    Ordering::Less => (lo + a * u, hi),
+                    Ordering::Greater => (lo, hi + a * u),
+                    Ordering::Equal => (lo, hi),
+                });
+            if !(lo..=hi).contains(&rhs) {
+                return None;
+            }
+            if rhs != lo && rhs != hi {
+                continue;
+            }
+            // At the lower end, every positive term is as small as possible
+            // and every negative term as large; the reverse at the upper end
+            for (var, (&a, &u)) in row.iter().zip(self.bounds.iter()).enumerate() {
+                if a != 0 {
+                    let at_max = (a > 0) == (rhs == hi);
+                    forced.push((var, if at_max { u } else { 0 }));
+                }
+            }
+        }
+        Some(forced)
+    }
+}
+
+impl Solver {
+    // Row-reduces the counts around the frontier, together with the total
+    // number of mines when the frontier is small enough, and labels every
+    // cell whose value the reduced rows force. Repeats until nothing changes.
+    // Returns false if `state` is found to be inconsistent.
+    pub(crate) fn linear_deduction(&self, state: &mut MinesweeperState) -> bool {
+        loop {
+            let forced = match self.linear_forced(state) {
+                Some(forced) => forced,
+                None => return false,
+            };
+            if forced.is_empty() {
+                return true;
+            }
+            for (idx, is_mine) in forced {
+                if state.get(idx) != Status::Unknown {
+                    continue;
+                }
+                let ok = if is_mine {
+                    state.set_flag(idx)
+                } else {
+                    state.set_mark(idx)
+                };
+                if !ok || !self.make_consistent_sq(idx, state) {
+                    return false;
+                }
+            }
+        }
+    }
+
+    fn linear_forced(&self, state: &MinesweeperState) -> Option<Vec<(Index, bool)>> {
+        let constraints = (0..self.size())
+            .filter(|&idx| {
+                state.get_known(idx).is_some()
+                    && self
+                        .square_of(state, idx, |s| s == Status::Unknown)
+                        .next()
+                        .is_some()
+            })
+            .collect::<Vec<Index>>();
+        let mut var_of = vec![None; self.size()];
+        let mut vars = Vec::new();
+        for &idx in &constraints {
+            for cidx in self.square_of(state, idx, |s| s == Status::Unknown) {
+                if var_of[cidx].is_none() {
+                    var_of[cidx] = Some(vars.len());
+                    vars.push(cidx);
+                }
+            }
+        }
+        let remainder = (0..self.size())
+            .filter(|&idx| state.get(idx) == Status::Unknown && var_of[idx].is_none())
+            .collect::<Vec<Index>>();
+        let target = |idx: Index| {
+            let flags = self.square_of(state, idx, |s| s == Status::Flagged).count();
+            state.get_known(idx).unwrap().checked_sub(flags)
+        };
+
+        let mut systems = Vec::new();
+        if vars.len() <= MAX_GLOBAL_VARIABLES {
+            // The remainder is one variable counting its mines
+            let mut bounds = vec![1; vars.len()];
+            bounds.push(remainder.len() as i64);
+            let mut system = System::new(bounds);
+            for &idx in &constraints {
+                let cells = self.square_of(state, idx, |s| s == Status::Unknown);
+                system.push(cells.filter_map(|cidx| var_of[cidx]), target(idx)?);
+            }
+            system.push(0..=vars.len(), state.flags_remaining());
+            // On overflow, fall back to the smaller systems of the components
+            match system.reduce() {
+                Some(()) => systems.push((system, (0..vars.len()).collect::<Vec<usize>>())),
+                None => log::debug!("Overflow in global linear deduction"),
+            }
+        }
+        if systems.is_empty() {
+            for component in self.linear_components(state, &constraints) {
+                let mut local = vec![None; self.size()];
+                let mut members = Vec::new();
+                for &idx in &component {
+                    for cidx in self.square_of(state, idx, |s| s == Status::Unknown) {
+                        if local[cidx].is_none() {
+                            local[cidx] = Some(members.len());
+                            members.push(var_of[cidx].unwrap());
+                        }
+                    }
+                }
+                let mut system = System::new(vec![1; members.len()]);
+                for &idx in &component {
+                    let cells = self.square_of(state, idx, |s| s == Status::Unknown);
+                    system.push(cells.filter_map(|cidx| local[cidx]), target(idx)?);
+                }
+                if system.reduce().is_none() {
+                    log::debug!("Overflow in linear deduction");
+                    continue;
+                }
+                systems.push((system, members));
+            }
+        }
+
+        let mut forced = Vec::new();
+        for (system, members) in systems {
+            for (var, value) in system.forced()? {
+                match members.get(var) {
+                    Some(&var) => forced.push((vars[var], value != 0)),
+                    None => forced.extend(remainder.iter().map(|&idx| (idx, value != 0))),
+                }
+            }
+        }
+        Some(forced)
+    }
+
+    // Groups the constraints that share an unknown cell
+    fn linear_components(
+        &self,
+        state: &MinesweeperState,
+        constraints: &[Index],
+    ) -> Vec<Vec<Index>> {
+        let mut seen = vec![false; self.size()];
+        let mut components = Vec::new();
+        for &start in constraints {
+            if std::mem::replace(&mut seen[start], true) {
+                continue;
+            }
+            let mut component = vec![start];
+            let mut i = 0;
+            while i < component.len() {
+                let idx = component[i];
+                i += 1;
+                for cidx in self.square_of(state, idx, |s| s == Status::Unknown) {
+                    for &next in self.square(cidx) {
+                        if state.get_known(next).is_some()
+                            && !std::mem::replace(&mut seen[next], true)
+                        {
+                            component.push(next);
+                        }
+                    }
+                }
+            }
+            components.push(component);
+        }
+        components
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+impl Solver {
+    // Among `guess` and the cells least likely to be a mine, up to
+    // `candidates` cells in total, picks the one most likely to be safe
+    // and to leave a certainly safe cell to reveal next.
+    pub(crate) fn lookahead(
+        &self,
+        state: &MinesweeperState,
+        v: &[Option<Real>],
+        guess: Index,
+        candidates: usize,
+    ) -> Index {
+        let mut cells = v
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, p)| Some((p.as_ref()?, idx)))
+            .filter(|&(_, idx)| idx != guess && state.get(idx) == Status::Unknown)
+            .collect::<Vec<(&Real, Index)>>();
+        cells.sort();
+        let mut best = (Real::zero(), guess);
+        for idx in iter::once(guess)
+            .chain(cells.into_iter().map(|(_, idx)| idx))
+            .take(candidates)
+        {
+            let score = match &v[idx] {
+                Some(p) => (Real::one() - p) * self.continuation(state, idx),
+                None => continue,
+            };
+            log::debug!("Lookahead {:03} {:?}", idx, score);
+            if score > best.0 {
+                best = (score, idx);
+            }
+        }
+        best.1
+    }
+
+    // Probability that revealing `idx`, given that it is safe, leaves
+    // either a certainly safe cell or nothing left to reveal
+    fn continuation(&self, state: &MinesweeperState, idx: Index) -> Real {
+        let mut total = Real::zero();
+        let mut progress = Real::zero();
+        for n in 0..=self.square(idx).len() {
+            let mut next = state.clone();
+            next.set_known(idx, n);
+            if !self.make_consistent_all(&mut next) {
+                continue;
+            }
+            if let Some((count, _, _)) = self.evaluate(&mut next) {
+                // An estimate says nothing about certainly safe cells, and
+                // outcomes cannot be weighed without their counts
+                let count = match count {
+                    Some(count) => count,
+                    None => return Real::zero(),
+                };
+                if next.unknowns() == 0 || next.board().contains(&Status::Marked) {
+                    progress += &count;
+                }
+                total += count;
+            }
+        }
+        if total.is_zero() {
+            total
+        } else {
+            progress / total
+        }
+    }
+}
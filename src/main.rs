@@ -35,10 +35,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     logger.init()?;
 
     let config = Config::from_difficulty(diff, seed);
-    let solver = Solver::new(config);
-    let mut inst = MockMinesweeper::new(config);
+    let solver = Solver::new(config.clone());
+    let mut inst = Game::new(config);
     while let Some(_) = solver.solve_next(&mut inst)? {
         println!("{}", ShowMinesweeper(&inst));
     }
+    println!("{:?} in {} moves", inst.status(), inst.moves());
     Ok(())
 }
@@ -24,25 +24,31 @@ pub struct MockMinesweeper {
 }
 
 impl MockMinesweeper {
+    /// A random board whose first click is safe at `Config::opening`, where
+    /// the solver opens.
     pub fn new(config: Config) -> Self {
         let state = MinesweeperState::new(&config);
-        let mut rng = config.new_rng();
-        let mut bombs = vec![false; config.size()];
-        for _ in 0..config.mines() {
-            loop {
-                let idx = config.random_index(&mut rng);
-                if idx != 0 && !bombs[idx] {
-                    bombs[idx] = true;
-                    break;
-                }
-            }
-        }
+        let first = config.opening().unwrap_or(0);
+        let bombs = Generator::new(config.clone()).generate(first);
         Self {
             bombs,
             config,
             state,
         }
     }
+
+    /// A board with the mines at the cells set in `bombs`, e.g. from an
+    /// imported `Replay`.
+    pub fn with_bombs(config: Config, bombs: Vec<bool>) -> MsResult<Self> {
+        if !config.is_layout(&bombs) {
+            return Err(MinesweeperError::NumberOfMinesOutOfRange);
+        }
+        Ok(Self {
+            bombs,
+            state: MinesweeperState::new(&config),
+            config,
+        })
+    }
 }
 
 impl Minesweeper for MockMinesweeper {
@@ -58,6 +64,14 @@ impl Minesweeper for MockMinesweeper {
         &self.state
     }
 
+    fn get_status(&self) -> GameStatus {
+        if self.state.knowns() + self.config.mines() == self.config.cells() {
+            GameStatus::Won
+        } else {
+            GameStatus::Playing
+        }
+    }
+
     fn pull(&self) -> MsResult<MinesweeperState> {
         Ok(self.state.clone())
     }
@@ -68,12 +82,33 @@ impl Minesweeper for MockMinesweeper {
         Ok(()) // Noop cuz mock
     }
 
+    fn unflag(&mut self, idx: usize) -> MsResult<()> {
+        debug_assert!(self.state.board()[idx] == Status::Flagged);
+        Ok(()) // Noop cuz mock
+    }
+
     fn reveal(&mut self, idx: usize) -> MsResult<()> {
         (!self.bombs[idx])
-            .then(|| self.state.reveal(idx, &self.bombs, self.config))
+            .then(|| self.state.reveal(idx, &self.bombs, &self.config))
             .ok_or(MinesweeperError::RevealedBomb(idx))
     }
 
+    fn chord(&mut self, idx: usize) -> MsResult<()> {
+        let cells = self
+            .state
+            .chord_cells(idx, &self.config)
+            .ok_or(MinesweeperError::InvalidMove(idx))?;
+        for cidx in cells {
+            if matches!(
+                self.state.get(cidx),
+                Status::Marked | Status::Question | Status::Unknown
+            ) {
+                self.reveal(cidx)?;
+            }
+        }
+        Ok(())
+    }
+
     fn set_internal(&mut self, state: MinesweeperState) -> MsResult<()> {
         self.state = state;
         Ok(())
@@ -0,0 +1,298 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+/// A certain move found by a local rule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Deduction {
+    action: Action,
+    technique: Technique,
+    constraints: Vec<Index>,
+}
+
+impl Deduction {
+    #[inline]
+    pub fn action(&self) -> Action {
+        self.action
+    }
+
+    #[inline]
+    pub fn technique(&self) -> Technique {
+        self.technique
+    }
+
+    /// The revealed cells whose counts the rule used.
+    #[inline]
+    pub fn constraints(&self) -> &[Index] {
+        &self.constraints
+    }
+}
+
+// The mines a revealed cell still needs and its unknown neighbours
+struct Count {
+    idx: Index,
+    mines: usize,
+    cells: Square,
+}
+
+impl Solver {
+    /// Every move the local rules find in `state`, without applying any of
+    /// them. Line patterns are tried first, then pairs of counts, then
+    /// single counts; each cell is reported once, with the first rule
+    /// that decided it.
+    pub fn deductions(&self, state: &MinesweeperState) -> Vec<Deduction> {
+        let mut state = state.clone();
+        state.clear_questions();
+        self.find_deductions(&state)
+    }
+
+    fn find_deductions(&self, state: &MinesweeperState) -> Vec<Deduction> {
+        let counts = (0..self.size())
+            .map(|idx| self.count(state, idx))
+            .collect::<Vec<Option<Count>>>();
+        let mut found = Vec::new();
+        for count in counts.iter().flatten() {
+            self.lines(state, &counts, count, &mut found);
+        }
+        for count in counts.iter().flatten() {
+            self.pairs(&counts, count, &mut found);
+        }
+        for count in counts.iter().flatten() {
+            if count.mines == 0 || count.mines == count.cells.len() {
+                let is_mine = count.mines != 0;
+                let cells = count.cells.iter().map(|&cidx| (cidx, is_mine));
+                push(&mut found, Technique::Single, &[count.idx], cells);
+            }
+        }
+        let mut seen = vec![false; self.size()];
+        found.retain(|d: &Deduction| !std::mem::replace(&mut seen[d.action.index()], true));
+        found
+    }
+
+    // Applies the local rules until none fires. Returns false if `state` is
+    // found to be inconsistent.
+    pub(crate) fn pattern_deduction(&self, state: &mut MinesweeperState) -> bool {
+        loop {
+            let found = self.find_deductions(state);
+            if found.is_empty() {
+                return true;
+            }
+            for deduction in found {
+                if state.get(deduction.action.index()) != Status::Unknown {
+                    continue;
+                }
+                log::debug!("{} {:?}", deduction.technique, deduction.action);
+                let ok = match deduction.action {
+                    Action::Flag(idx) => state.set_flag(idx),
+                    Action::Reveal(idx) => state.set_mark(idx),
+                    Action::Unflag(_) | Action::Question(_) | Action::Chord(_) => unreachable!(),
+                };
+                if !ok || !self.make_consistent_sq(deduction.action.index(), state) {
+                    return false;
+                }
+            }
+        }
+    }
+
+    fn count(&self, state: &MinesweeperState, idx: Index) -> Option<Count> {
+        let count = state.get_known(idx)?;
+        let flags = self.square_of(state, idx, |s| s == Status::Flagged).count();
+        let cells = self
+            .square_of(state, idx, |s| s == Status::Unknown)
+            .collect::<Square>();
+        let mines = count.checked_sub(flags)?;
+        (!cells.is_empty() && mines <= cells.len()).then(|| Count { idx, mines, cells })
+    }
+
+    // Subset and superset rules between `a` and every count sharing an
+    // unknown cell with it. Along an edge these are the 1-1 and 1-2 patterns.
+    fn pairs(&self, counts: &[Option<Count>], a: &Count, found: &mut Vec<Deduction>) {
+        let others = a
+            .cells
+            .iter()
+            .flat_map(|&cidx| self.square(cidx).iter())
+            .filter(|&&bidx| bidx != a.idx)
+            .unique()
+            .filter_map(|&bidx| counts[bidx].as_ref());
+        for b in others {
+            let only_a = a.cells.iter().filter(|x| !b.cells.contains(x)).copied();
+            let only_b = b
+                .cells
+                .iter()
+                .filter(|x| !a.cells.contains(x))
+                .copied()
+                .collect::<Square>();
+            let shared = b.cells.len() - only_b.len();
+            let edge = self.is_edge_pair(a, b);
+            if shared == a.cells.len() && !only_b.is_empty() && b.mines >= a.mines {
+                // The cells of `a` hold exactly `a.mines` of the mines of `b`
+                let extra = b.mines - a.mines;
+                let technique = match (edge, a.mines, b.mines) {
+                    (true, 1, 1) => Technique::OneOne,
+                    _ => Technique::Subset,
+                };
+                if extra == 0 || extra == only_b.len() {
+                    let cells = only_b.iter().map(|&cidx| (cidx, extra != 0));
+                    push(found, technique, &[a.idx, b.idx], cells);
+                }
+            } else if shared < a.cells.len()
+                && shared >= a.mines
+                && b.mines >= a.mines
+                && b.mines - a.mines == only_b.len()
+            {
+                // The shared cells hold at most `a.mines` of the mines of
+                // `b`, so the rest need every cell only `b` sees
+                let technique = match (edge, a.mines, b.mines) {
+                    (true, 1, 2) => Technique::OneTwo,
+                    _ => Technique::Superset,
+                };
+                let cells = only_b
+                    .iter()
+                    .map(|&cidx| (cidx, true))
+                    .chain(only_a.map(|cidx| (cidx, false)));
+                push(found, technique, &[a.idx, b.idx], cells);
+            }
+        }
+    }
+
+    // Whether `a` and `b` are orthogonally adjacent on a square grid with
+    // every unknown cell of both on the same side of the line through them
+    fn is_edge_pair(&self, a: &Count, b: &Count) -> bool {
+        let config = self.config();
+        if !is_square_grid(config) {
+            return false;
+        }
+        let along = [(0, 1), (0, -1), (1, 0), (-1, 0)]
+            .iter()
+            .copied()
+            .find(|&(dr, dc)| config.offset(a.idx, dr, dc) == Some(b.idx));
+        let (dr, dc) = match along {
+            Some(along) => along,
+            None => return false,
+        };
+        // The line beside `a` and `b`, from one cell before `a` to one cell
+        // after `b`
+        [1, -1].iter().any(|&side| {
+            let beside = (-1..=2)
+                .filter_map(|i| config.offset(a.idx, side * dc + i * dr, side * dr + i * dc))
+                .collect::<Vec<Index>>();
+            a.cells
+                .iter()
+                .chain(b.cells.iter())
+                .all(|idx| beside.contains(idx))
+        })
+    }
+
+    // The 1-2-1 and 1-2-2-1 patterns along a line of a square grid starting
+    // at `a`, with all unknown cells in the next line on one side
+    fn lines(
+        &self,
+        state: &MinesweeperState,
+        counts: &[Option<Count>],
+        a: &Count,
+        found: &mut Vec<Deduction>,
+    ) {
+        const PATTERNS: [(Technique, &[usize], &[usize]); 2] = [
+            (Technique::OneTwoOne, &[1, 2, 1], &[1, 3]),
+            (Technique::OneTwoTwoOne, &[1, 2, 2, 1], &[2, 3]),
+        ];
+        let config = self.config();
+        if !is_square_grid(config) {
+            return;
+        }
+        for (along, side) in [
+            ((0, 1), (1, 0)),
+            ((0, 1), (-1, 0)),
+            ((1, 0), (0, 1)),
+            ((1, 0), (0, -1)),
+        ] {
+            // Cells of the line and of the side line, the latter starting one
+            // step before the line does
+            let line = |i: isize| config.offset(a.idx, along.0 * i, along.1 * i);
+            let beside = |i: isize| {
+                config.offset(
+                    a.idx,
+                    side.0 + along.0 * (i - 1),
+                    side.1 + along.1 * (i - 1),
+                )
+            };
+            // A wrapped line must not reach its own start
+            let span = if along.0 == 0 {
+                config.length()
+            } else {
+                config.width()
+            };
+            for &(technique, mines, at) in PATTERNS.iter() {
+                let len = mines.len() as isize;
+                if config.is_wrapped() && span < mines.len() + 2 {
+                    continue;
+                }
+                let cells = (0..len)
+                    .map(|i| line(i).and_then(|idx| counts[idx].as_ref()))
+                    .collect::<Option<Vec<&Count>>>();
+                let cells = match cells {
+                    Some(cells) => cells,
+                    None => continue,
+                };
+                let fits = cells
+                    .iter()
+                    .zip(mines.iter())
+                    .enumerate()
+                    .all(|(i, (count, &m))| {
+                        let i = i as isize;
+                        let window = (i..i + 3).filter_map(beside).collect::<Square>();
+                        let inner = i != 0 && i != len - 1;
+                        count.mines == m
+                            && count.cells.iter().all(|x| window.contains(x))
+                            && (!inner || window.len() == 3 && count.cells.len() == 3)
+                    });
+                if !fits {
+                    continue;
+                }
+                let constraints = cells.iter().map(|count| count.idx).collect::<Vec<Index>>();
+                let result = (0..len + 2)
+                    .filter_map(|i| Some((beside(i)?, at.contains(&(i as usize)))))
+                    .filter(|&(idx, _)| state.get(idx) == Status::Unknown);
+                push(found, technique, &constraints, result);
+            }
+        }
+    }
+}
+
+fn push(
+    found: &mut Vec<Deduction>,
+    technique: Technique,
+    constraints: &[Index],
+    cells: impl Iterator<Item = (Index, bool)>,
+) {
+    found.extend(cells.map(|(idx, is_mine)| Deduction {
+        action: if is_mine {
+            Action::Flag(idx)
+        } else {
+            Action::Reveal(idx)
+        },
+        technique,
+        constraints: constraints.to_vec(),
+    }));
+}
+
+// Whether the cells are laid out on a single square grid, which the line
+// patterns rely on
+fn is_square_grid(config: &Config) -> bool {
+    config.grid() == Grid::Square && config.depth() == 1 && config.topology().is_none()
+}
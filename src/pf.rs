@@ -18,14 +18,14 @@
 use super::*;
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct PF(Vec<R64>);
+pub struct PF(Vec<Real>);
 
 impl fmt::Debug for PF {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "[")?;
         for (i, p) in self.0.iter().enumerate() {
-            if p > &R64::new(0.0) {
-                write!(f, "{:02}: {:.3}; ", i, p.raw())?;
+            if p > &Real::zero() {
+                write!(f, "{:02}: {:.3}; ", i, util::to_f64(p))?;
             }
         }
         write!(f, "]")?;
@@ -35,12 +35,12 @@ impl fmt::Debug for PF {
 
 impl PF {
     pub fn one_hot(x: usize) -> Self {
-        let mut v = vec![R64::new(0.0); x + 1];
-        v[x] = R64::new(1.0);
+        let mut v = vec![Real::zero(); x + 1];
+        v[x] = Real::one();
         PF(v)
     }
 
-    pub fn zip_with_longest<'a, F: 'a + Fn(EitherOrBoth<&R64, &R64>) -> R64>(
+    pub fn zip_with_longest<'a, F: 'a + Fn(EitherOrBoth<&Real, &Real>) -> Real>(
         &self,
         rhs: &Self,
         f: F,
@@ -54,73 +54,75 @@ impl PF {
     }
 
     pub fn convolve(&self, rhs: &Self) -> Self {
-        let mut v = vec![R64::new(0.0); self.0.len() + rhs.0.len() + 1];
-        for (i, &x) in self.0.iter().enumerate() {
-            for (j, &y) in rhs.0.iter().enumerate() {
-                v[i + j] += x * y;
+        let mut v = vec![Real::zero(); self.0.len() + rhs.0.len() + 1];
+        for (i, x) in self.0.iter().enumerate() {
+            for (j, y) in rhs.0.iter().enumerate() {
+                v[i + j] += x.clone() * y;
             }
         }
         PF(v)
     }
 
-    pub fn ev(&self) -> R64 {
+    pub fn ev(&self) -> Real {
         self.0
             .iter()
             .enumerate()
-            .map(|(idx, &p)| p * R64::new(idx as f64))
-            .sum::<R64>()
+            .map(|(idx, p)| p.clone() * util::real(idx))
+            .fold(Real::zero(), Add::add)
     }
 
     // Precision?
     pub fn normalize(&mut self) {
-        let s = self.0.iter().sum::<R64>();
-        if s > R64::new(0.0) {
+        let s = self.sum();
+        if s > Real::zero() {
             for p in self.0.iter_mut() {
-                *p *= R64::new(1.0) / s;
+                *p = p.clone() / &s;
             }
         }
     }
 
-    pub fn label(&self, state: &mut MinesweeperState, idx: usize) -> bool {
-        if self.0.len() > 1 && self.0.iter().skip(1).all(|&p| p == R64::new(1.0)) {
-            return state.set_flag(idx);
+    // Weighs entry `i` by the ways to place the other `flags - i` mines
+    // among `n` cells, relative to the most ways so that no weight
+    // overflows. Returns the most ways, if it fits in `Real`.
+    pub fn weighted(&mut self, flags: usize, n: usize) -> Option<Real> {
+        let (lo, hi) = (flags.saturating_sub(n), min(flags, self.0.len() - 1));
+        let mut weights = vec![Real::zero(); self.0.len()];
+        if lo > hi {
+            self.0 = weights;
+            return Some(Real::zero());
         }
-        if self.0.iter().all(|&p| p == R64::new(0.0)) {
-            return state.set_mark(idx);
+        // The ways peak where `flags - i` is nearest to `n / 2`
+        let peak = flags.saturating_sub(n / 2).clamp(lo, hi);
+        weights[peak] = Real::one();
+        for i in peak + 1..=hi {
+            let m = flags - i + 1;
+            weights[i] = weights[i - 1].clone() * util::real(m) / util::real(n - m + 1);
         }
-        true
-    }
-
-    pub fn weighted(&mut self, flags: usize, n: usize) {
-        for (i, p) in self.0.iter_mut().enumerate() {
-            if i <= flags && flags <= n + i {
-                *p *= util::binomial(n, flags - i);
-            } else {
-                *p = R64::new(0.0);
-            }
+        for i in (lo..peak).rev() {
+            let m = flags - i - 1;
+            weights[i] = weights[i + 1].clone() * util::real(n - m) / util::real(m + 1);
         }
-        self.normalize();
+        for (p, w) in self.0.iter_mut().zip(weights) {
+            *p = p.clone() * w;
+        }
+        util::checked_binomial(n, flags - peak)
     }
 
-    pub fn sum(&self) -> R64 {
-        self.0.iter().sum::<R64>()
+    pub fn sum(&self) -> Real {
+        self.0.iter().fold(Real::zero(), |acc, p| acc + p)
     }
 }
 
 impl Default for PF {
     fn default() -> Self {
-        PF(vec![R64::new(0.0)])
+        PF(vec![Real::zero()])
     }
 }
 
 impl Add for PF {
     type Output = Self;
     fn add(self, rhs: Self) -> Self {
-        self.zip_with_longest(&rhs, |either| match either {
-            EitherOrBoth::Both(&c, &d) => c + d,
-            EitherOrBoth::Left(&c) => c,
-            EitherOrBoth::Right(&d) => d,
-        })
+        &self + &rhs
     }
 }
 
@@ -128,9 +130,9 @@ impl Add for &PF {
     type Output = PF;
     fn add(self, rhs: Self) -> PF {
         self.zip_with_longest(rhs, |either| match either {
-            EitherOrBoth::Both(&c, &d) => c + d,
-            EitherOrBoth::Left(&c) => c,
-            EitherOrBoth::Right(&d) => d,
+            EitherOrBoth::Both(c, d) => c.clone() + d,
+            EitherOrBoth::Left(c) => c.clone(),
+            EitherOrBoth::Right(d) => d.clone(),
         })
     }
 }
@@ -138,10 +140,7 @@ impl Add for &PF {
 impl Mul for PF {
     type Output = Self;
     fn mul(self, rhs: Self) -> Self {
-        self.zip_with_longest(&rhs, |either| match either {
-            EitherOrBoth::Both(&c, &d) => c * d,
-            _ => R64::new(0.0),
-        })
+        &self * &rhs
     }
 }
 
@@ -149,8 +148,8 @@ impl Mul for &PF {
     type Output = PF;
     fn mul(self, rhs: Self) -> PF {
         self.zip_with_longest(rhs, |either| match either {
-            EitherOrBoth::Both(&c, &d) => c * d,
-            _ => R64::new(0.0),
+            EitherOrBoth::Both(c, d) => c.clone() * d,
+            _ => Real::zero(),
         })
     }
 }
@@ -158,19 +157,16 @@ impl Mul for &PF {
 impl Div for PF {
     type Output = Self;
     fn div(self, rhs: Self) -> Self {
-        self.zip_with_longest(&rhs, |either| match either {
-            EitherOrBoth::Both(&c, &d) if d != R64::new(0.0) => c / d,
-            _ => R64::new(0.0),
-        })
+        &self / &rhs
     }
 }
 
 impl Div for &PF {
     type Output = PF;
     fn div(self, rhs: Self) -> PF {
-        self.zip_with_longest(&rhs, |either| match either {
-            EitherOrBoth::Both(&c, &d) if d != R64::new(0.0) => c / d,
-            _ => R64::new(0.0),
+        self.zip_with_longest(rhs, |either| match either {
+            EitherOrBoth::Both(c, d) if !d.is_zero() => c.clone() / d,
+            _ => Real::zero(),
         })
     }
-}
\ No newline at end of file
+}
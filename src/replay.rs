@@ -0,0 +1,421 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+const HEADER: &str = "minesweeper replay 1";
+
+/// A recorded game: its `Config`, its mines and every action applied to
+/// it, with the time since the game started.
+///
+/// Written by `to_text` in, and parsed from, a line-based text format:
+///
+/// ```text
+/// minesweeper replay 1
+/// config <width> <length> <mines> <seed> [depth <depth>] [wrap] [hex|knight|radius2]
+/// [masked <idx> <idx> ...]
+/// mines <idx> <idx> ...
+/// <millis> <action> <idx>
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replay {
+    config: Config,
+    bombs: Vec<bool>,
+    actions: Vec<(Duration, Action)>,
+}
+
+impl Replay {
+    /// A replay of `actions` on a board with the mines at the cells set in
+    /// `bombs`, which must hold exactly `config.mines()` of them, none on a
+    /// masked cell.
+    pub fn new(
+        config: Config,
+        bombs: Vec<bool>,
+        actions: Vec<(Duration, Action)>,
+    ) -> MsResult<Self> {
+        if !config.is_layout(&bombs) {
+            return Err(MinesweeperError::NumberOfMinesOutOfRange);
+        }
+        Ok(Self {
+            config,
+            bombs,
+            actions,
+        })
+    }
+
+    #[inline]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    #[inline]
+    pub fn bombs(&self) -> &[bool] {
+        &self.bombs
+    }
+
+    #[inline]
+    pub fn actions(&self) -> &[(Duration, Action)] {
+        &self.actions
+    }
+
+    /// The replay in the text format. Fails with `UnsupportedLayout` for a
+    /// custom topology, which the format cannot express.
+    pub fn to_text(&self) -> MsResult<String> {
+        let config = &self.config;
+        if config.topology().is_some() {
+            return Err(MinesweeperError::UnsupportedLayout);
+        }
+        let mut text = format!(
+            "{}\nconfig {} {} {} {}",
+            HEADER,
+            config.width(),
+            config.length(),
+            config.mines(),
+            config.seed()
+        );
+        if config.depth() > 1 {
+            text += &format!(" depth {}", config.depth());
+        }
+        if config.is_wrapped() {
+            text += " wrap";
+        }
+        text += match config.grid() {
+            Grid::Square => "",
+            Grid::Hex => " hex",
+            Grid::Knight => " knight",
+            Grid::Radius2 => " radius2",
+        };
+        text += "\n";
+        let list = |cells: &mut dyn Iterator<Item = Index>| {
+            cells.map(|idx| format!(" {}", idx)).collect::<String>()
+        };
+        if config.cells() < config.size() {
+            let mut masked = (0..config.size()).filter(|&idx| config.is_masked(idx));
+            text += &format!("masked{}\n", list(&mut masked));
+        }
+        let mut mines = (0..self.bombs.len()).filter(|&idx| self.bombs[idx]);
+        text += &format!("mines{}\n", list(&mut mines));
+        for (time, action) in &self.actions {
+            text += &format!("{} {}\n", time.as_millis(), action);
+        }
+        Ok(text)
+    }
+}
+
+impl FromStr for Replay {
+    type Err = MinesweeperError;
+
+    /// Parse errors hold the line number, counting from 1.
+    fn from_str(s: &str) -> MsResult<Self> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .peekable();
+        expect(&mut lines, HEADER)?;
+        let (i, rest) = expect(&mut lines, "config ")?;
+        let mut words = rest.split_whitespace();
+        let fields = words
+            .by_ref()
+            .take(4)
+            .map(|x| x.parse::<u64>().ok())
+            .collect::<Option<Vec<u64>>>();
+        let mut words = words.peekable();
+        let depth = match words.next_if_eq(&"depth") {
+            Some(_) => words
+                .next()
+                .and_then(|depth| depth.parse().ok())
+                .ok_or(MinesweeperError::ParseError(i))?,
+            None => 1,
+        };
+        let mut config = match fields.as_deref() {
+            Some(&[width, length, mines, seed]) => Config::new_3d(
+                width as usize,
+                length as usize,
+                depth,
+                mines as usize,
+                Some(seed),
+            )
+            .map_err(|_| MinesweeperError::ParseError(i))?,
+            _ => return Err(MinesweeperError::ParseError(i)),
+        };
+        for word in words {
+            config = match word {
+                "wrap" => config.with_wrap(),
+                "hex" => config.with_grid(Grid::Hex),
+                "knight" => config.with_grid(Grid::Knight),
+                "radius2" => config.with_grid(Grid::Radius2),
+                _ => return Err(MinesweeperError::ParseError(i)),
+            }
+            .map_err(|_| MinesweeperError::ParseError(i))?;
+        }
+        if lines
+            .peek()
+            .is_some_and(|(_, line)| line.starts_with("masked"))
+        {
+            let (i, rest) = expect(&mut lines, "masked")?;
+            let mask = cells(rest, config.size()).ok_or(MinesweeperError::ParseError(i))?;
+            config = config
+                .with_mask(mask)
+                .map_err(|_| MinesweeperError::ParseError(i))?;
+        }
+        let (i, rest) = expect(&mut lines, "mines")?;
+        let bombs = cells(rest, config.size()).ok_or(MinesweeperError::ParseError(i))?;
+        let actions = lines
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(i, line)| {
+                let (time, action) = line
+                    .split_once(' ')
+                    .ok_or(MinesweeperError::ParseError(i))?;
+                let time = time.parse().map_err(|_| MinesweeperError::ParseError(i))?;
+                let action = action
+                    .parse::<Action>()
+                    .ok()
+                    .filter(|action| action.index() < config.size())
+                    .ok_or(MinesweeperError::ParseError(i))?;
+                Ok((Duration::from_millis(time), action))
+            })
+            .collect::<MsResult<Vec<_>>>()?;
+        Self::new(config, bombs, actions).map_err(|_| MinesweeperError::ParseError(i))
+    }
+}
+
+// The rest of the next line, which must start with `prefix`
+fn expect<'a>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    prefix: &str,
+) -> MsResult<(usize, &'a str)> {
+    let (i, line) = lines.next().ok_or(MinesweeperError::ParseError(0))?;
+    let rest = line
+        .strip_prefix(prefix)
+        .ok_or(MinesweeperError::ParseError(i))?;
+    Ok((i, rest))
+}
+
+// The cells listed in `s` out of `size`
+fn cells(s: &str, size: usize) -> Option<Vec<bool>> {
+    let mut cells = vec![false; size];
+    for idx in s.split_whitespace() {
+        match idx.parse::<Index>() {
+            Ok(idx) if idx < size => cells[idx] = true,
+            _ => return None,
+        }
+    }
+    Some(cells)
+}
+
+/// A `Game` that records every action applied to it, for `into_replay`.
+/// Actions that fail are not recorded.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    game: Game,
+    start: Instant,
+    actions: Vec<(Duration, Action)>,
+}
+
+impl Recorder {
+    pub fn new(game: Game) -> Self {
+        Self {
+            game,
+            start: Instant::now(),
+            actions: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn apply(&mut self, action: Action) -> MsResult<()> {
+        self.game.apply(action)?;
+        self.actions.push((self.start.elapsed(), action));
+        Ok(())
+    }
+
+    /// The game so far. Fails if no cell has been revealed yet, as the mines
+    /// are only placed then.
+    pub fn into_replay(self) -> MsResult<Replay> {
+        let bombs = self
+            .game
+            .placed_bombs()
+            .ok_or(MinesweeperError::InconsistentState)?
+            .to_vec();
+        Replay::new(self.game.get_config().clone(), bombs, self.actions)
+    }
+}
+
+impl Minesweeper for Recorder {
+    fn get_bombs(&self) -> Option<&[bool]> {
+        self.game.get_bombs()
+    }
+
+    fn get_config(&self) -> &Config {
+        self.game.get_config()
+    }
+
+    fn get_state(&self) -> &MinesweeperState {
+        self.game.get_state()
+    }
+
+    fn get_status(&self) -> GameStatus {
+        self.game.get_status()
+    }
+
+    fn pull(&self) -> MsResult<MinesweeperState> {
+        self.game.pull()
+    }
+
+    fn flag(&mut self, idx: Index) -> MsResult<()> {
+        self.apply(Action::Flag(idx))
+    }
+
+    fn unflag(&mut self, idx: Index) -> MsResult<()> {
+        self.apply(Action::Unflag(idx))
+    }
+
+    fn reveal(&mut self, idx: Index) -> MsResult<()> {
+        self.apply(Action::Reveal(idx))
+    }
+
+    fn chord(&mut self, idx: Index) -> MsResult<()> {
+        self.apply(Action::Chord(idx))
+    }
+
+    fn set_internal(&mut self, state: MinesweeperState) -> MsResult<()> {
+        self.game.set_internal(state)
+    }
+}
+
+/// Plays a `Replay` back on a fresh `Game` with the recorded mines.
+///
+/// Either `step` through the recording, or drive it through `Minesweeper`,
+/// e.g. with a solver, to compare a new build against the recorded game.
+/// The first action that differs from the recording is kept as the
+/// divergence.
+#[derive(Clone, Debug)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    game: Game,
+    position: usize,
+    divergence: Option<usize>,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> MsResult<Self> {
+        let game = Game::with_bombs(replay.config.clone(), replay.bombs.clone())?;
+        Ok(Self {
+            replay,
+            game,
+            position: 0,
+            divergence: None,
+        })
+    }
+
+    #[inline]
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    #[inline]
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Number of actions applied so far.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.replay.actions.len()
+    }
+
+    /// Position of the first action that differed from the recording.
+    #[inline]
+    pub fn divergence(&self) -> Option<usize> {
+        self.divergence
+    }
+
+    /// The next recorded action, with the time it was made.
+    pub fn peek(&self) -> Option<(Duration, Action)> {
+        self.replay.actions.get(self.position).copied()
+    }
+
+    /// Applies the next recorded action and returns it, or `None` at the end
+    /// of the recording.
+    pub fn step(&mut self) -> MsResult<Option<Action>> {
+        match self.peek() {
+            Some((_, action)) => self.apply(action).map(|_| Some(action)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn apply(&mut self, action: Action) -> MsResult<()> {
+        if self.divergence.is_none() && self.peek().map(|(_, a)| a) != Some(action) {
+            log::debug!("Diverged from replay at {} with {}", self.position, action);
+            self.divergence = Some(self.position);
+        }
+        self.game.apply(action)?;
+        self.position += 1;
+        Ok(())
+    }
+}
+
+impl Minesweeper for ReplayPlayer {
+    fn get_bombs(&self) -> Option<&[bool]> {
+        self.game.get_bombs()
+    }
+
+    fn get_config(&self) -> &Config {
+        self.game.get_config()
+    }
+
+    fn get_state(&self) -> &MinesweeperState {
+        self.game.get_state()
+    }
+
+    fn get_status(&self) -> GameStatus {
+        self.game.get_status()
+    }
+
+    fn pull(&self) -> MsResult<MinesweeperState> {
+        self.game.pull()
+    }
+
+    fn flag(&mut self, idx: Index) -> MsResult<()> {
+        self.apply(Action::Flag(idx))
+    }
+
+    fn unflag(&mut self, idx: Index) -> MsResult<()> {
+        self.apply(Action::Unflag(idx))
+    }
+
+    fn reveal(&mut self, idx: Index) -> MsResult<()> {
+        self.apply(Action::Reveal(idx))
+    }
+
+    fn chord(&mut self, idx: Index) -> MsResult<()> {
+        self.apply(Action::Chord(idx))
+    }
+
+    fn set_internal(&mut self, state: MinesweeperState) -> MsResult<()> {
+        self.game.set_internal(state)
+    }
+}
@@ -0,0 +1,360 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+// Sweeps spent looking for consistent layouts per sample requested
+// before giving up
+const MAX_SWEEPS_PER_SAMPLE: usize = 100;
+
+/// Settings of the Markov chain run by `Solver::sample`.
+///
+/// The chain moves mines between unknown cells, keeping the total fixed,
+/// and accepts a move that breaks `d` more constraints with probability
+/// `exp(-beta * d)`. Only layouts breaking no constraint are recorded, so
+/// the recorded layouts are uniform over the consistent ones.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sampler {
+    samples: usize,
+    sweeps: usize,
+    burn_in: usize,
+    beta: f64,
+    seed: Option<u64>,
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self {
+            samples: 1000,
+            sweeps: 1,
+            burn_in: 100,
+            beta: 2.0,
+            seed: None,
+        }
+    }
+}
+
+impl Sampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of consistent layouts to record.
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Sweeps between recorded layouts. A sweep proposes one move per
+    /// unknown cell.
+    pub fn with_sweeps(mut self, sweeps: usize) -> Self {
+        self.sweeps = sweeps;
+        self
+    }
+
+    /// Sweeps run before the first layout is recorded.
+    pub fn with_burn_in(mut self, burn_in: usize) -> Self {
+        self.burn_in = burn_in;
+        self
+    }
+
+    /// Inverse temperature. Higher values stay closer to consistent layouts
+    /// but mix more slowly.
+    pub fn with_beta(mut self, beta: f64) -> Self {
+        self.beta = beta;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+/// Mine probabilities estimated from sampled layouts, as computed by
+/// `Solver::sample`. Revealed cells have no probability.
+#[derive(Clone, Debug)]
+pub struct Estimate {
+    samples: usize,
+    hits: Vec<Option<usize>>,
+}
+
+impl Estimate {
+    /// Number of layouts the estimate is based on.
+    #[inline]
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.hits.len()
+    }
+
+    pub fn probabilities(&self) -> Vec<Option<f64>> {
+        (0..self.size()).map(|idx| self.probability(idx)).collect()
+    }
+
+    #[inline]
+    pub fn probability(&self, idx: Index) -> Option<f64> {
+        Some(self.hits[idx]? as f64 / self.samples as f64)
+    }
+
+    /// Wilson score interval of the probability at `idx` for the standard
+    /// normal quantile `z`, e.g. 1.96 for 95%. Treats the samples as
+    /// independent, so it is only honest with enough sweeps between them.
+    pub fn interval(&self, idx: Index, z: f64) -> Option<(f64, f64)> {
+        let p = self.probability(idx)?;
+        let n = self.samples as f64;
+        let z2 = z * z;
+        let centre = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let spread = z / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+        Some(((centre - spread).max(0.0), (centre + spread).min(1.0)))
+    }
+}
+
+// A layout of the unknown cells, with how far it is from satisfying each
+// constraint
+struct Chain {
+    // For every unknown cell, the constraints it appears in
+    constraints: Vec<Square>,
+    // For every unknown cell, the unknown cells sharing a constraint with it
+    nearby: Vec<Vec<usize>>,
+    targets: Vec<usize>,
+    sums: Vec<usize>,
+    is_mine: Vec<bool>,
+    // Position of every cell in `mines` or `free`
+    slot: Vec<usize>,
+    mines: Vec<usize>,
+    free: Vec<usize>,
+    energy: usize,
+}
+
+impl Chain {
+    fn new(
+        constraints: Vec<Square>,
+        nearby: Vec<Vec<usize>>,
+        targets: Vec<usize>,
+        mines: usize,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let n = constraints.len();
+        let mut order = (0..n).collect::<Vec<usize>>();
+        for i in (1..n).rev() {
+            order.swap(i, rng.gen_range(0..=i));
+        }
+        let mut chain = Self {
+            constraints,
+            nearby,
+            sums: vec![0; targets.len()],
+            targets,
+            is_mine: vec![false; n],
+            slot: vec![0; n],
+            mines: Vec::with_capacity(mines),
+            free: Vec::with_capacity(n - mines),
+            energy: 0,
+        };
+        for (i, &var) in order.iter().enumerate() {
+            if i < mines {
+                chain.is_mine[var] = true;
+                chain.slot[var] = chain.mines.len();
+                chain.mines.push(var);
+                for &c in &chain.constraints[var] {
+                    chain.sums[c] += 1;
+                }
+            } else {
+                chain.slot[var] = chain.free.len();
+                chain.free.push(var);
+            }
+        }
+        chain.energy = chain
+            .sums
+            .iter()
+            .zip(chain.targets.iter())
+            .map(|(&s, &t)| s.abs_diff(t))
+            .sum();
+        chain
+    }
+
+    // Adds or removes a mine at `var`, returning the change in energy
+    fn shift(&mut self, var: usize, add: bool) -> isize {
+        let mut delta = 0;
+        for &c in &self.constraints[var] {
+            let before = self.sums[c].abs_diff(self.targets[c]) as isize;
+            if add {
+                self.sums[c] += 1;
+            } else {
+                self.sums[c] -= 1;
+            }
+            delta += self.sums[c].abs_diff(self.targets[c]) as isize - before;
+        }
+        delta
+    }
+
+    fn swap(&mut self, mine: usize, free: usize) -> isize {
+        let delta = self.shift(mine, false) + self.shift(free, true);
+        let (i, j) = (self.slot[mine], self.slot[free]);
+        self.mines[i] = free;
+        self.free[j] = mine;
+        self.slot.swap(mine, free);
+        self.is_mine.swap(mine, free);
+        delta
+    }
+
+    // One Metropolis-Hastings step. Half the proposals move a mine to a cell
+    // sharing a constraint with it, which repairs local violations quickly;
+    // the others move it anywhere, which keeps the chain irreducible.
+    fn step(&mut self, beta: f64, rng: &mut impl Rng) {
+        if self.mines.is_empty() || self.free.is_empty() {
+            return;
+        }
+        let mine = self.mines[rng.gen_range(0..self.mines.len())];
+        let (free, ratio) = if rng.gen::<bool>() {
+            if self.nearby[mine].is_empty() {
+                return;
+            }
+            let free = self.nearby[mine][rng.gen_range(0..self.nearby[mine].len())];
+            if self.is_mine[free] {
+                return;
+            }
+            let ratio = self.nearby[mine].len() as f64 / self.nearby[free].len() as f64;
+            (free, ratio)
+        } else {
+            (self.free[rng.gen_range(0..self.free.len())], 1.0)
+        };
+        let delta = self.swap(mine, free);
+        let accept = ratio * (-beta * delta as f64).exp();
+        if accept < 1.0 && rng.gen::<f64>() >= accept {
+            self.swap(free, mine);
+        } else {
+            self.energy = (self.energy as isize + delta) as usize;
+        }
+    }
+
+    fn sweep(&mut self, beta: f64, rng: &mut impl Rng) {
+        for _ in 0..self.constraints.len() {
+            self.step(beta, rng);
+        }
+    }
+}
+
+impl Solver {
+    /// Estimates the mine probability of every cell by sampling layouts
+    /// consistent with `state` and the number of mines left. Unlike
+    /// `analyze`, the cost does not depend on the size of the frontier.
+    pub fn sample(&self, state: &MinesweeperState, sampler: &Sampler) -> MsResult<Estimate> {
+        let mut state = state.clone();
+        state.clear_questions();
+        if !self.make_consistent_all(&mut state) {
+            return Err(MinesweeperError::InconsistentState);
+        }
+        let flags = state.flags_remaining();
+        let vars = (0..self.size())
+            .filter(|&idx| state.get(idx) == Status::Unknown)
+            .collect::<Vec<Index>>();
+        if flags > vars.len() {
+            return Err(MinesweeperError::InconsistentState);
+        }
+        let mut var_of = vec![None; self.size()];
+        for (var, &idx) in vars.iter().enumerate() {
+            var_of[idx] = Some(var);
+        }
+
+        let mut targets = Vec::new();
+        let mut constraints = vec![Square::new(); vars.len()];
+        for idx in 0..self.size() {
+            let count = match state.get_known(idx) {
+                Some(count) => count,
+                None => continue,
+            };
+            let flagged = self
+                .square_of(&state, idx, |s| s == Status::Flagged)
+                .count();
+            let cells = self
+                .square(idx)
+                .iter()
+                .filter_map(|&cidx| var_of[cidx])
+                .collect::<Square>();
+            if cells.is_empty() {
+                continue;
+            }
+            let target = count
+                .checked_sub(flagged)
+                .ok_or(MinesweeperError::InconsistentState)?;
+            for &var in &cells {
+                constraints[var].push(targets.len());
+            }
+            targets.push(target);
+        }
+        let mut members = vec![Vec::new(); targets.len()];
+        for (var, cs) in constraints.iter().enumerate() {
+            for &c in cs {
+                members[c].push(var);
+            }
+        }
+        let nearby = constraints
+            .iter()
+            .enumerate()
+            .map(|(var, cs)| {
+                cs.iter()
+                    .flat_map(|&c| members[c].iter().copied())
+                    .filter(|&other| other != var)
+                    .unique()
+                    .collect()
+            })
+            .collect();
+
+        let mut rng = match sampler.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let mut chain = Chain::new(constraints, nearby, targets, flags, &mut rng);
+        for _ in 0..sampler.burn_in {
+            chain.sweep(sampler.beta, &mut rng);
+        }
+
+        let mut hits = vec![0; vars.len()];
+        let mut samples = 0;
+        let mut budget = sampler.samples.saturating_mul(MAX_SWEEPS_PER_SAMPLE);
+        while samples < sampler.samples && budget > 0 {
+            for _ in 0..sampler.sweeps.max(1) {
+                chain.sweep(sampler.beta, &mut rng);
+                budget = budget.saturating_sub(1);
+            }
+            if chain.energy == 0 {
+                samples += 1;
+                for &var in &chain.mines {
+                    hits[var] += 1;
+                }
+            }
+        }
+        log::debug!("Sampled {} layouts", samples);
+        if samples == 0 {
+            return Err(MinesweeperError::InconsistentState);
+        }
+
+        let hits = (0..self.size())
+            .map(|idx| match state.get(idx) {
+                Status::Known(_) | Status::Masked => None,
+                Status::Flagged => Some(samples),
+                Status::Marked => Some(0),
+                Status::Question | Status::Unknown => var_of[idx].map(|var| hits[var]),
+            })
+            .collect();
+        Ok(Estimate { samples, hits })
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+// An applied action with the cells it changed, before and after, and the
+// generator that placed the mines if it did
+#[derive(Clone, Debug)]
+struct Entry {
+    action: Action,
+    cells: Vec<(Index, Status, Status)>,
+    status: (GameStatus, GameStatus),
+    moves: (usize, usize),
+    generator: Option<Generator>,
+}
+
+/// A `Game` that records every action applied to it, so that it can be
+/// rewound and replayed. Applying an action after an undo drops the undone
+/// actions. Undoing the first reveal also takes back the mines it placed,
+/// and redoing it places them again as before.
+#[derive(Clone, Debug)]
+pub struct Session {
+    game: Game,
+    history: Vec<Entry>,
+    position: usize,
+}
+
+impl Session {
+    pub fn new(game: Game) -> Self {
+        Self {
+            game,
+            history: Vec::new(),
+            position: 0,
+        }
+    }
+
+    #[inline]
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Number of actions applied at the current point in history.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Number of actions recorded, including undone ones.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.history.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.history.is_empty()
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = Action> + '_ {
+        self.history.iter().map(|entry| entry.action)
+    }
+
+    /// Applies `action` to the game. Actions that change nothing are not
+    /// recorded.
+    pub fn apply(&mut self, action: Action) -> MsResult<()> {
+        let before = self.game.get_state().clone();
+        let status = self.game.status();
+        let moves = self.game.moves();
+        let generator = self.game.generator().cloned();
+        self.game.apply(action)?;
+        let generator = generator.filter(|_| self.game.generator().is_none());
+        let cells = before
+            .board()
+            .iter()
+            .zip(self.game.get_state().board().iter())
+            .enumerate()
+            .filter(|(_, (x, y))| x != y)
+            .map(|(idx, (&x, &y))| (idx, x, y))
+            .collect::<Vec<(Index, Status, Status)>>();
+        if cells.is_empty() && status == self.game.status() {
+            return Ok(());
+        }
+        self.history.truncate(self.position);
+        self.history.push(Entry {
+            action,
+            cells,
+            status: (status, self.game.status()),
+            moves: (moves, self.game.moves()),
+            generator,
+        });
+        self.position += 1;
+        Ok(())
+    }
+
+    /// Takes back the last applied action. False if there is none.
+    pub fn undo(&mut self) -> bool {
+        if self.position == 0 {
+            return false;
+        }
+        self.position -= 1;
+        let entry = &self.history[self.position];
+        let cells = entry.cells.iter().map(|&(idx, before, _)| (idx, before));
+        self.game.restore(cells, entry.status.0, entry.moves.0);
+        if let Some(generator) = &entry.generator {
+            self.game.unplace(generator.clone());
+        }
+        true
+    }
+
+    /// Applies again the last undone action. False if there is none.
+    pub fn redo(&mut self) -> bool {
+        if self.position == self.history.len() {
+            return false;
+        }
+        let entry = &self.history[self.position];
+        let cells = entry.cells.iter().map(|&(idx, _, after)| (idx, after));
+        self.game.restore(cells, entry.status.1, entry.moves.1);
+        if entry.generator.is_some() {
+            self.game.place(entry.action.index());
+        }
+        self.position += 1;
+        true
+    }
+
+    /// Moves to the point in history after `position` actions. False if
+    /// there are not that many.
+    pub fn jump(&mut self, position: usize) -> bool {
+        if position > self.history.len() {
+            return false;
+        }
+        while self.position > position {
+            self.undo();
+        }
+        while self.position < position {
+            self.redo();
+        }
+        true
+    }
+}
+
+impl Minesweeper for Session {
+    fn get_bombs(&self) -> Option<&[bool]> {
+        self.game.get_bombs()
+    }
+
+    fn get_config(&self) -> &Config {
+        self.game.get_config()
+    }
+
+    fn get_state(&self) -> &MinesweeperState {
+        self.game.get_state()
+    }
+
+    fn get_status(&self) -> GameStatus {
+        self.game.get_status()
+    }
+
+    fn pull(&self) -> MsResult<MinesweeperState> {
+        self.game.pull()
+    }
+
+    fn flag(&mut self, idx: usize) -> MsResult<()> {
+        self.apply(Action::Flag(idx))
+    }
+
+    fn unflag(&mut self, idx: usize) -> MsResult<()> {
+        self.apply(Action::Unflag(idx))
+    }
+
+    fn reveal(&mut self, idx: usize) -> MsResult<()> {
+        self.apply(Action::Reveal(idx))
+    }
+
+    fn chord(&mut self, idx: usize) -> MsResult<()> {
+        self.apply(Action::Chord(idx))
+    }
+
+    fn set_internal(&mut self, state: MinesweeperState) -> MsResult<()> {
+        self.game.set_internal(state)
+    }
+}
@@ -38,9 +38,23 @@ impl<'a> fmt::Display for ShowState<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let flags = self.state.flags_remaining();
         writeln!(f, "[Remain {:02} flags] {:?}", flags, self.config)?;
+        // Counts of 10 or more take three columns, so then every cell does
+        let wide = (0..self.config.size()).any(|idx| self.config.square(idx).count() >= 10);
         for (idx, status) in self.state.board().iter().enumerate() {
+            // One slice per layer of a 3D board
+            let (layer, row, col) = self.config.as_lrc(idx);
+            if self.config.depth() > 1 && row == 0 && col == 0 {
+                write!(f, "\n\n[Layer {}]", layer)?;
+            }
             if idx % self.config.length() == 0 {
                 write!(f, "\n")?;
+                // Odd rows of a hex grid sit half a cell to the right
+                if self.config.grid() == Grid::Hex && row % 2 == 1 {
+                    write!(f, " ")?;
+                }
+            }
+            if wide && !matches!(status, Status::Known(_)) {
+                write!(f, " ")?;
             }
             if self
                 .bombs
@@ -52,14 +66,17 @@ impl<'a> fmt::Display for ShowState<'a> {
                     Status::Flagged => write!(f, "🚩")?,
                     Status::Known(_) => unreachable!("Is bomb"),
                     Status::Marked => unreachable!("Wrong solution"),
-                    Status::Unknown => write!(f, "💣")?,
+                    Status::Masked => unreachable!("Is masked"),
+                    Status::Question | Status::Unknown => write!(f, "💣")?,
                 }
             } else {
                 match status {
                     Status::Flagged => write!(f, "🏁")?,
-                    Status::Known(x) => write!(f, "{}.", x)?,
+                    Status::Known(x) => write!(f, "{:>1$}.", x, if wide { 2 } else { 1 })?,
                     Status::Marked => write!(f, "✅")?,
+                    Status::Question => write!(f, "❓")?,
                     Status::Unknown => write!(f, "❔")?,
+                    Status::Masked => write!(f, "  ")?,
                 }
             }
         }
@@ -21,18 +21,59 @@ use super::*;
 pub struct Solver {
     config: Config,
     squares: Vec<Square>,
+    opening: Option<Index>,
+    strategy: Arc<dyn GuessStrategy>,
+    lookahead: usize,
+    budget: Budget,
+    cache: Arc<Cache>,
 }
 
 impl Solver {
     pub fn new(config: Config) -> Self {
         Self {
-            config,
             squares: (0..config.size())
                 .map(|idx| config.square(idx).collect())
                 .collect(),
+            opening: config.opening(),
+            config,
+            strategy: Arc::new(Heuristic),
+            lookahead: 0,
+            budget: Budget::unlimited(),
+            cache: Arc::default(),
         }
     }
 
+    pub fn with_strategy<G: 'static + GuessStrategy>(mut self, strategy: G) -> Self {
+        self.strategy = Arc::new(strategy);
+        self
+    }
+
+    /// Guess by searching one move ahead among at most `candidates` cells
+    /// instead of using the guess strategy alone. Disabled with zero.
+    pub fn with_lookahead(mut self, candidates: usize) -> Self {
+        self.lookahead = candidates;
+        self
+    }
+
+    /// Bounds the model counting done per position. Once the budget is
+    /// spent, probabilities are estimated from the local densities instead.
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Component evaluations kept between moves. Shared with clones.
+    #[inline]
+    pub fn cache(&self) -> &Cache {
+        &self.cache
+    }
+
+    #[inline]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    #[inline]
     pub fn size(&self) -> usize {
         self.squares.len()
     }
@@ -41,7 +82,7 @@ impl Solver {
         &self.squares[idx]
     }
 
-    fn square_of<'a, F: 'static + Fn(Status) -> bool>(
+    pub(crate) fn square_of<'a, F: 'static + Fn(Status) -> bool>(
         &'a self,
         state: &'a MinesweeperState,
         idx: Index,
@@ -87,48 +128,64 @@ impl Solver {
             .unwrap_or(true)
     }
 
-    fn make_consistent_sq(&self, idx: Index, state: &mut MinesweeperState) -> bool {
+    pub(crate) fn make_consistent_sq(&self, idx: Index, state: &mut MinesweeperState) -> bool {
         self.square(idx)
             .iter()
             .all(|&cidx| self.make_consistent(cidx, state))
     }
 
-    fn make_consistent_all(&self, state: &mut MinesweeperState) -> bool {
+    pub(crate) fn make_consistent_all(&self, state: &mut MinesweeperState) -> bool {
         (0..self.size()).all(|cidx| self.make_consistent(cidx, state))
     }
 
-    fn branching_evaluation(&self, state: &MinesweeperState, group: &Group) -> Option<Evaluation> {
+    pub(crate) fn branching_evaluation(
+        &self,
+        state: &MinesweeperState,
+        group: &Group,
+        meter: &Meter,
+    ) -> Option<Evaluation> {
+        if !meter.spend() {
+            return None;
+        }
         let idx = group.get()?;
         let mut sf = state.clone();
         let mut sm = state.clone();
 
         // Short circuiting
         let sf = (sf.set_flag(idx) && self.make_consistent_sq(idx, &mut sf))
-            .then(|| self.splitting_evaluation(&sf, group))
+            .then(|| self.splitting_evaluation(&sf, group, meter))
             .flatten();
         let sm = (sm.set_mark(idx) && self.make_consistent_sq(idx, &mut sm))
-            .then(|| self.splitting_evaluation(&sm, group))
+            .then(|| self.splitting_evaluation(&sm, group, meter))
             .flatten();
+        // A branch cut short by the budget would leave out its layouts
+        if meter.exhausted() {
+            return None;
+        }
         util::lift(Evaluation::add)(sf, sm)
     }
 
-    fn splitting_evaluation(&self, state: &MinesweeperState, group: &Group) -> Option<Evaluation> {
+    fn splitting_evaluation(
+        &self,
+        state: &MinesweeperState,
+        group: &Group,
+        meter: &Meter,
+    ) -> Option<Evaluation> {
         let (group, remainder) = group.trim(state);
         let eval = Evaluation::new(state, remainder);
         match group {
             Some(group) => group.into_iter().fold(Some(eval), |eval, split| {
-                Some(eval? * self.branching_evaluation(state, &split)?)
+                Some(eval? * self.branching_evaluation(state, &split, meter)?)
             }),
             None => Some(eval),
         }
     }
 
+    // Opens the game at `Config::opening` while nothing is revealed
     fn corner_search(&self, state: &MinesweeperState) -> Option<ScoredIndex> {
-        match state.get(0) {
-            Status::Known(_) => None,
-            Status::Flagged | Status::Marked => unreachable!(),
-            _ => Some((R64::new(0.0), 0)),
-        }
+        let opening = self.opening?;
+        let unopened = state.knowns() == 0 && state.get(opening) == Status::Unknown;
+        unopened.then(|| (Real::zero(), opening))
     }
 
     fn fast_search(state: &MinesweeperState) -> Option<ScoredIndex> {
@@ -136,49 +193,128 @@ impl Solver {
             .board()
             .iter()
             .position(|status| status == &Status::Marked)?;
-        Some((R64::new(0.0), idx))
+        Some((Real::zero(), idx))
     }
 
-    fn solve_state(&self, state: &mut MinesweeperState) -> Option<ScoredIndex> {
-        util::catch(move || {
+    // Labels every certain cell of `state` and returns the number of
+    // consistent layouts, whether the probabilities are exact, and the mine
+    // probability of each cell left unknown, or `None` if `state` is
+    // inconsistent. If the budget runs out, the probabilities are only
+    // estimates and the count is `None`, as it is when it does not fit in
+    // a float without the `exact` feature.
+    pub(crate) fn evaluate(
+        &self,
+        state: &mut MinesweeperState,
+    ) -> Option<(Option<Real>, bool, Vec<Option<Real>>)> {
+        let mut v = vec![None; self.size()];
+        let (group, remainder) = Group::new(self, state);
+        let n = remainder.count_ones();
+        let flags = state.flags_remaining();
+        let meter = Meter::new(self.budget);
+        let (count, exact, bp) = match group {
+            Some(group) => match self.cached_evaluation(state, &group, &meter) {
+                Some(eval) => {
+                    debug_assert!(!meter.exhausted());
+                    log::debug!("{:?}", eval);
+                    let (bp, ps) = eval.to_probabilities(flags, n);
+                    for (p, idx) in ps {
+                        v[idx] = Some(p);
+                    }
+                    (eval.configurations(flags, n), true, bp)
+                }
+                None if meter.exhausted() => {
+                    log::info!("Budget exhausted, estimating probabilities");
+                    for (p, idx) in self.estimate(state) {
+                        v[idx] = Some(p);
+                    }
+                    let sum = v.iter().flatten().fold(Real::zero(), |acc, p| acc + p);
+                    let bp = (n != 0).then(|| {
+                        let bp = (util::real(flags) - sum) / util::real(n);
+                        num_traits::clamp(bp, Real::zero(), Real::one())
+                    });
+                    (None, false, bp)
+                }
+                None => return None,
+            },
+            None => {
+                if flags > n {
+                    return None;
+                }
+                let bp = (n != 0).then(|| util::real(flags) / util::real(n));
+                (util::checked_binomial(n, flags), true, bp)
+            }
+        };
+        for idx in remainder.iter_ones() {
+            v[idx] = bp.clone();
+        }
+        if !exact {
+            return Some((count, exact, v));
+        }
+        for (idx, p) in v.iter_mut().enumerate() {
+            match p {
+                Some(q) if q.is_zero() => state.set_mark(idx),
+                Some(q) if q.is_one() => state.set_flag(idx),
+                _ => continue,
+            };
+            *p = None;
+        }
+        Some((count, exact, v))
+    }
+
+    pub(crate) fn guess(
+        &self,
+        state: &MinesweeperState,
+        v: &[Option<Real>],
+    ) -> Option<ScoredIndex> {
+        let idx = self.strategy.guess(self, state, v)?;
+        let idx = match self.lookahead {
+            0 => idx,
+            n => self.lookahead(state, v, idx, n),
+        };
+        Some((v[idx].clone()?, idx))
+    }
+
+    fn solve_state(&self, state: &mut MinesweeperState) -> Option<Move> {
+        let mut exact = true;
+        let scored_index = util::catch(|| {
             util::wrap(self.corner_search(state))?;
             util::wrap(Self::fast_search(state))?;
             util::guard(self.make_consistent_all(state))?;
             util::wrap(Self::fast_search(state))?;
-            let (group, remainder) = Group::new(&self, state);
-            let eval = util::guard_from(|| self.branching_evaluation(state, group.as_ref()?))?;
-            eval.label(state);
+            util::guard(self.pattern_deduction(state))?;
             util::wrap(Self::fast_search(state))?;
-            log::debug!("{:?}", eval);
-
-            let mut v = vec![None; self.size()];
-            let (bp, ps) = eval.to_probabilities(state.flags_remaining(), remainder.count_ones());
-            for (p, idx) in ps {
-                v[idx] = Some(p);
-            }
-            for idx in remainder.iter_ones() {
-                v[idx] = bp;
-            }
-            util::wrap(v.iter()
-                .enumerate()
-                .filter_map(|(idx, p)| Some((*p.as_ref()?, idx)))
-                .min_by_key(|(p, idx)| {
-                    let p0 = self
-                        .square(*idx)
-                        .iter()
-                        .filter_map(|&cidx| Some(R64::new(1.0) - v[cidx]?))
-                        .product::<R64>();
-                    *p * (R64::new(1.0) - p0)
-                })
-            )?;
+            util::guard(self.linear_deduction(state))?;
+            util::wrap(Self::fast_search(state))?;
+            let (_, evaluated, v) = util::guard_from(|| self.evaluate(state))?;
+            exact = evaluated;
+            util::wrap(Self::fast_search(state))?;
+            util::wrap(self.guess(state, &v))?;
             Ok(())
-        })
+        })?;
+        Some(Move::new(scored_index, exact))
+    }
+
+    pub fn analyze(&self, state: &MinesweeperState) -> MsResult<Analysis> {
+        let mut state = state.clone();
+        state.clear_questions();
+        let (count, exact, v) = self
+            .make_consistent_all(&mut state)
+            .then(|| self.evaluate(&mut state))
+            .flatten()
+            .ok_or(MinesweeperError::InconsistentState)?;
+        Ok(Analysis::new(&state, count, exact, v))
     }
 
-    pub fn solve_next<T: Minesweeper>(&self, sweep: &mut T) -> MsResult<Option<ScoredIndex>> {
+    /// Opens the next cell of `sweep` and returns it, or `None` if the game
+    /// is over or no cell can be opened.
+    pub fn solve_next<T: Minesweeper>(&self, sweep: &mut T) -> MsResult<Option<Move>> {
+        if sweep.get_status() != GameStatus::Playing {
+            return Ok(None);
+        }
         let mut state = sweep.pull()?;
-        let scored_index = self.solve_state(&mut state);
-        log::info!("{:?}", scored_index);
+        state.clear_questions();
+        let next = self.solve_state(&mut state);
+        log::info!("{:?}", next);
         let indices = sweep
             .get_state()
             .board()
@@ -192,17 +328,35 @@ impl Solver {
         for idx in indices {
             sweep.flag(idx)?;
         }
+        let chord = next
+            .as_ref()
+            .filter(|next| next.probability().is_zero())
+            .and_then(|next| self.chord_for(&state, next.index()));
         sweep.set_internal(state)?;
-        if let Some((_, idx)) = scored_index {
-            sweep.reveal(idx)?;
+        match (chord, next.as_ref()) {
+            (Some(cidx), _) => sweep.chord(cidx)?,
+            (None, Some(next)) => sweep.reveal(next.index())?,
+            (None, None) => (),
         }
-        Ok(scored_index)
+        Ok(next)
+    }
+
+    // A revealed neighbour of `idx` whose chord reveals `idx` and at least
+    // one more cell, all of them certainly safe. Prefers the largest chord.
+    fn chord_for(&self, state: &MinesweeperState, idx: Index) -> Option<Index> {
+        self.square(idx)
+            .iter()
+            .filter_map(|&cidx| {
+                let cells = state.chord_cells(cidx, &self.config)?;
+                let safe = cells.iter().all(|&x| state.get(x) == Status::Marked);
+                (safe && cells.len() > 1).then_some((cells.len(), cidx))
+            })
+            .max()
+            .map(|(_, cidx)| cidx)
     }
 
     pub fn solve<T: Minesweeper>(&self, sweep: &mut T) -> MsResult<()> {
-        while let Some(x) = self.solve_next(sweep)? {
-            drop(x);
-        }
+        while self.solve_next(sweep)?.is_some() {}
         Ok(())
     }
 }
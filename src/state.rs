@@ -17,7 +17,15 @@
 
 use super::*;
 
+/// With the `serde` feature, serializes as the board and the number of
+/// flags remaining. The other counters are recomputed from the board when
+/// deserializing.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "StateData", into = "StateData")
+)]
 pub struct MinesweeperState {
     board: Vec<Status>,
     flags_remaining: usize,
@@ -25,13 +33,60 @@ pub struct MinesweeperState {
     knowns: usize,
 }
 
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StateData {
+    board: Vec<Status>,
+    flags_remaining: usize,
+}
+
+#[cfg(feature = "serde")]
+impl From<MinesweeperState> for StateData {
+    fn from(state: MinesweeperState) -> Self {
+        Self {
+            board: state.board,
+            flags_remaining: state.flags_remaining,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<StateData> for MinesweeperState {
+    type Error = MinesweeperError;
+
+    fn try_from(data: StateData) -> MsResult<Self> {
+        let count = |f: fn(&Status) -> bool| data.board.iter().filter(|s| f(s)).count();
+        let unknowns = count(|s| matches!(s, Status::Question | Status::Unknown));
+        let knowns = count(|s| matches!(s, Status::Known(_)));
+        // Every unflagged mine needs an unrevealed cell
+        if data.flags_remaining > unknowns + count(|s| s == &Status::Marked) {
+            return Err(MinesweeperError::InconsistentState);
+        }
+        Ok(Self {
+            board: data.board,
+            flags_remaining: data.flags_remaining,
+            unknowns,
+            knowns,
+        })
+    }
+}
+
 impl MinesweeperState {
+    /// Masked cells are `Status::Masked` and are not counted as unknown.
     pub fn new(config: &Config) -> Self {
-        let board = vec![Status::Unknown; config.size()];
+        let board = (0..config.size())
+            .map(|idx| {
+                if config.is_masked(idx) {
+                    Status::Masked
+                } else {
+                    Status::Unknown
+                }
+            })
+            .collect();
         Self {
             board,
             flags_remaining: config.mines(),
-            unknowns: config.size(),
+            unknowns: config.cells(),
             knowns: 0,
         }
     }
@@ -62,7 +117,10 @@ impl MinesweeperState {
 
     #[inline]
     pub fn set_flag(&mut self, idx: Index) -> bool {
-        debug_assert_eq!(self.board[idx], Status::Unknown);
+        debug_assert!(matches!(
+            self.board[idx],
+            Status::Question | Status::Unknown
+        ));
         if self.unknowns > 0 && self.flags_remaining() > 0 {
             self.board[idx] = Status::Flagged;
             self.unknowns -= 1;
@@ -73,6 +131,27 @@ impl MinesweeperState {
         }
     }
 
+    #[inline]
+    pub fn unset_flag(&mut self, idx: Index) {
+        debug_assert_eq!(self.board[idx], Status::Flagged);
+        self.board[idx] = Status::Unknown;
+        self.unknowns += 1;
+        self.flags_remaining += 1;
+    }
+
+    /// Question marks count as unknown cells.
+    #[inline]
+    pub fn set_question(&mut self, idx: Index) {
+        debug_assert_eq!(self.board[idx], Status::Unknown);
+        self.board[idx] = Status::Question;
+    }
+
+    #[inline]
+    pub fn unset_question(&mut self, idx: Index) {
+        debug_assert_eq!(self.board[idx], Status::Question);
+        self.board[idx] = Status::Unknown;
+    }
+
     #[inline]
     pub fn set_mark(&mut self, idx: Index) -> bool {
         debug_assert_eq!(self.board[idx], Status::Unknown);
@@ -85,6 +164,41 @@ impl MinesweeperState {
         }
     }
 
+    // Overwrites the status of `idx`, keeping the counters in step
+    pub(crate) fn set_status(&mut self, idx: Index, status: Status) {
+        match self.board[idx] {
+            Status::Flagged => self.flags_remaining += 1,
+            Status::Known(_) => self.knowns -= 1,
+            Status::Marked | Status::Masked => (),
+            Status::Question | Status::Unknown => self.unknowns -= 1,
+        }
+        match status {
+            Status::Flagged => self.flags_remaining -= 1,
+            Status::Known(_) => self.knowns += 1,
+            Status::Marked | Status::Masked => (),
+            Status::Question | Status::Unknown => self.unknowns += 1,
+        }
+        self.board[idx] = status;
+    }
+
+    pub fn clear_marks(&mut self) {
+        for status in self.board.iter_mut() {
+            if *status == Status::Marked {
+                *status = Status::Unknown;
+                self.unknowns += 1;
+            }
+        }
+    }
+
+    // The solver reads question marks as plain unknown cells
+    pub(crate) fn clear_questions(&mut self) {
+        for status in self.board.iter_mut() {
+            if *status == Status::Question {
+                *status = Status::Unknown;
+            }
+        }
+    }
+
     pub fn filter_status<'a>(
         &'a self,
         square: &'a Square,
@@ -108,20 +222,56 @@ impl MinesweeperState {
         }
     }
 
-    pub fn reveal(&mut self, idx: Index, bombs: &[bool], config: Config) {
-        debug_assert!(matches!(self.get(idx), Status::Marked | Status::Unknown));
+    #[inline]
+    pub fn set_known(&mut self, idx: Index, count: usize) {
+        debug_assert!(matches!(
+            self.get(idx),
+            Status::Marked | Status::Question | Status::Unknown
+        ));
+        if matches!(self.board[idx], Status::Question | Status::Unknown) {
+            self.unknowns -= 1;
+        }
+        self.board[idx] = Status::Known(count);
+        self.knowns += 1;
+    }
+
+    // The unrevealed, unflagged neighbours of `idx` if it is revealed and
+    // has exactly as many flags around it as its count
+    pub fn chord_cells(&self, idx: Index, config: &Config) -> Option<Vec<Index>> {
+        let count = self.get_known(idx)?;
+        let flags = config
+            .square(idx)
+            .filter(|&cidx| self.get(cidx) == Status::Flagged)
+            .count();
+        (count == flags).then(|| {
+            config
+                .square(idx)
+                .filter(|&cidx| {
+                    matches!(
+                        self.get(cidx),
+                        Status::Marked | Status::Question | Status::Unknown
+                    )
+                })
+                .collect()
+        })
+    }
+
+    pub fn reveal(&mut self, idx: Index, bombs: &[bool], config: &Config) {
+        debug_assert!(matches!(
+            self.get(idx),
+            Status::Marked | Status::Question | Status::Unknown
+        ));
         if !bombs[idx] {
             let count = config.square(idx).filter(|&cidx| bombs[cidx]).count();
-            if self.board[idx] == Status::Unknown {
-                self.unknowns -= 1;
-            }
-            self.board[idx] = Status::Known(count);
-            self.knowns += 1;
+            self.set_known(idx, count);
             if count != 0 {
                 return;
             }
             for cidx in config.square(idx) {
-                if matches!(self.get(cidx), Status::Marked | Status::Unknown) {
+                if matches!(
+                    self.get(cidx),
+                    Status::Marked | Status::Question | Status::Unknown
+                ) {
                     self.reveal(cidx, bombs, config);
                 }
             }
@@ -0,0 +1,163 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+/// Chooses the cell to reveal when no cell is certainly safe.
+pub trait GuessStrategy: fmt::Debug + Send + Sync + RefUnwindSafe {
+    /// `probabilities` holds the mine probability of every unknown cell
+    /// of `state`, indexed like the board.
+    fn guess(
+        &self,
+        solver: &Solver,
+        state: &MinesweeperState,
+        probabilities: &[Option<Real>],
+    ) -> Option<Index>;
+}
+
+fn candidates(probabilities: &[Option<Real>]) -> impl Iterator<Item = (Index, &Real)> {
+    probabilities
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, p)| Some((idx, p.as_ref()?)))
+}
+
+// Distribution of the number revealed at `idx`, treating the unknown
+// neighbours as independent
+fn number_distribution(
+    solver: &Solver,
+    state: &MinesweeperState,
+    probabilities: &[Option<Real>],
+    idx: Index,
+) -> Vec<f64> {
+    let flags = solver
+        .square(idx)
+        .iter()
+        .filter(|&&cidx| state.get(cidx) == Status::Flagged)
+        .count();
+    let mut v = vec![0.0; flags + 1];
+    v[flags] = 1.0;
+    for p in solver
+        .square(idx)
+        .iter()
+        .filter_map(|&cidx| probabilities[cidx].as_ref())
+    {
+        let p = util::to_f64(p);
+        let mut w = vec![0.0; v.len() + 1];
+        for (i, q) in v.iter().enumerate() {
+            w[i] += q * (1.0 - p);
+            w[i + 1] += q * p;
+        }
+        v = w;
+    }
+    v
+}
+
+/// Reveals the cell least likely to be a mine.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MinProbability;
+
+impl GuessStrategy for MinProbability {
+    fn guess(
+        &self,
+        _: &Solver,
+        _: &MinesweeperState,
+        probabilities: &[Option<Real>],
+    ) -> Option<Index> {
+        candidates(probabilities)
+            .min_by(|(_, p), (_, q)| p.cmp(q))
+            .map(|(idx, _)| idx)
+    }
+}
+
+/// Minimises the mine probability times the chance that some neighbour is
+/// a mine, which favours cells likely to open up a region. The default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Heuristic;
+
+impl GuessStrategy for Heuristic {
+    fn guess(
+        &self,
+        solver: &Solver,
+        _: &MinesweeperState,
+        probabilities: &[Option<Real>],
+    ) -> Option<Index> {
+        candidates(probabilities)
+            .min_by_key(|&(idx, p)| {
+                let p0 = solver
+                    .square(idx)
+                    .iter()
+                    .filter_map(|&cidx| Some(Real::one() - probabilities[cidx].as_ref()?))
+                    .fold(Real::one(), Mul::mul);
+                p.clone() * (Real::one() - p0)
+            })
+            .map(|(idx, _)| idx)
+    }
+}
+
+/// Maximises the expected entropy of the number revealed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MaxInformation;
+
+impl GuessStrategy for MaxInformation {
+    fn guess(
+        &self,
+        solver: &Solver,
+        state: &MinesweeperState,
+        probabilities: &[Option<Real>],
+    ) -> Option<Index> {
+        candidates(probabilities)
+            .map(|(idx, p)| {
+                let entropy = number_distribution(solver, state, probabilities, idx)
+                    .into_iter()
+                    .filter(|&q| q > 0.0)
+                    .map(|q| -q * q.log2())
+                    .sum::<f64>();
+                (idx, (1.0 - util::to_f64(p)) * entropy)
+            })
+            .max_by(|(_, x), (_, y)| x.total_cmp(y))
+            .map(|(idx, _)| idx)
+    }
+}
+
+/// Maximises the expected number of cells revealed by the click, counting
+/// the unknown neighbours of a zero as revealed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MaxProgress;
+
+impl GuessStrategy for MaxProgress {
+    fn guess(
+        &self,
+        solver: &Solver,
+        state: &MinesweeperState,
+        probabilities: &[Option<Real>],
+    ) -> Option<Index> {
+        candidates(probabilities)
+            .map(|(idx, p)| {
+                let unknowns = solver
+                    .square(idx)
+                    .iter()
+                    .filter(|&&cidx| probabilities[cidx].is_some())
+                    .count();
+                let p0 = number_distribution(solver, state, probabilities, idx)[0];
+                let progress = 1.0 + p0 * unknowns as f64;
+                (idx, (1.0 - util::to_f64(p)) * progress)
+            })
+            .max_by(|(_, x), (_, y)| x.total_cmp(y))
+            .map(|(idx, _)| idx)
+    }
+}
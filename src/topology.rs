@@ -0,0 +1,103 @@
+// Copyright (C) 2021 Marcus Xu
+//
+// This file is part of minesweeper.
+//
+// minesweeper is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// minesweeper is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with minesweeper.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+/// The cells of a board and which of them neighbour each other. A revealed
+/// cell counts the mines among its neighbours.
+///
+/// Every `Config` is a topology. Others are played with through
+/// `Config::from_topology`.
+pub trait Topology: fmt::Debug + Send + Sync + RefUnwindSafe {
+    fn size(&self) -> usize;
+
+    /// The neighbours of `idx`, not including `idx`. If `a` neighbours `b`,
+    /// `b` must neighbour `a`.
+    fn neighbours(&self, idx: Index) -> SmallVec<[Index; 8]>;
+}
+
+impl Topology for Config {
+    fn size(&self) -> usize {
+        Config::size(self)
+    }
+
+    fn neighbours(&self, idx: Index) -> SmallVec<[Index; 8]> {
+        self.square(idx).collect()
+    }
+}
+
+/// A board given by the neighbours of every cell.
+///
+/// Parses from text with one line per cell, listing the indices of its
+/// neighbours separated by spaces. Lines starting with `#` are skipped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Graph {
+    neighbours: Vec<Square>,
+}
+
+impl Graph {
+    /// Fails with the first cell that neighbours itself, a cell that does
+    /// not exist, or a cell that does not neighbour it back.
+    pub fn new(neighbours: Vec<Vec<Index>>) -> MsResult<Self> {
+        let size = neighbours.len();
+        for (idx, square) in neighbours.iter().enumerate() {
+            let valid = square
+                .iter()
+                .all(|&cidx| cidx < size && cidx != idx && neighbours[cidx].contains(&idx))
+                && square.iter().all_unique();
+            if !valid {
+                return Err(MinesweeperError::InvalidTopology(idx));
+            }
+        }
+        Ok(Self {
+            neighbours: neighbours
+                .into_iter()
+                .map(|square| square.into_iter().collect())
+                .collect(),
+        })
+    }
+}
+
+impl Topology for Graph {
+    fn size(&self) -> usize {
+        self.neighbours.len()
+    }
+
+    fn neighbours(&self, idx: Index) -> SmallVec<[Index; 8]> {
+        self.neighbours[idx].clone()
+    }
+}
+
+impl FromStr for Graph {
+    type Err = MinesweeperError;
+
+    /// Parse errors hold the line number, counting from 1.
+    fn from_str(s: &str) -> MsResult<Self> {
+        let neighbours = s
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim_start().starts_with('#'))
+            .map(|(i, line)| {
+                line.split_whitespace()
+                    .map(|cidx| cidx.parse::<Index>())
+                    .collect::<Result<Vec<Index>, _>>()
+                    .map_err(|_| MinesweeperError::ParseError(i + 1))
+            })
+            .collect::<MsResult<Vec<Vec<Index>>>>()?;
+        Self::new(neighbours)
+    }
+}
@@ -17,11 +17,36 @@
 
 use super::*;
 
-pub fn binomial(n: usize, r: usize) -> R64 {
+pub fn real(x: usize) -> Real {
+    Real::from_usize(x).unwrap()
+}
+
+pub fn to_f64(x: &Real) -> f64 {
+    x.to_f64().unwrap_or(f64::NAN)
+}
+
+pub fn binomial(n: usize, r: usize) -> Real {
     debug_assert!(n >= r);
-    (1..=r)
-        .map(|x| R64::new(1.0 + ((n - r) as f64) / (x as f64)))
-        .product::<R64>()
+    (1..=r).fold(Real::one(), |acc, x| acc * real(n - r + x) / real(x))
+}
+
+// `binomial(n, r)`, or `None` if it does not fit in a float without the
+// `exact` feature. `binomial` multiplies before dividing, so its partial
+// products can reach `n` times the result.
+pub fn checked_binomial(n: usize, r: usize) -> Option<Real> {
+    let ln = (1..=r)
+        .map(|x| ((n - r + x) as f64 / x as f64).ln())
+        .sum::<f64>();
+    let fits = ln + (n.max(1) as f64).ln() < f64::MAX.ln();
+    (cfg!(feature = "exact") || fits).then(|| binomial(n, r))
+}
+
+pub fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
 }
 
 pub fn lift<T, F: Fn(T, T) -> T>(f: F) -> impl Fn(Option<T>, Option<T>) -> Option<T> {